@@ -0,0 +1,2 @@
+-- Required gatekeepers get the same --var variables as the one requiring them
+return require("is_work_dir")
@@ -0,0 +1,2 @@
+-- Follows cache_source; sync evaluates cache_source first so both agree
+return cached("cache_source", false)
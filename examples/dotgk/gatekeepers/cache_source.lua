@@ -0,0 +1,2 @@
+-- Read by cache_mirror through cached()
+return true
@@ -0,0 +1,3 @@
+-- debounce: 3
+-- A newly cached false only turns true after three evaluations agree
+return true
@@ -0,0 +1,6 @@
+-- dir() returns each gatekeeper's own value, not just booleans
+local seen = {}
+for _, theme in ipairs(dir("themes")) do
+  seen[theme] = true
+end
+return seen.dark == true and seen.light == true
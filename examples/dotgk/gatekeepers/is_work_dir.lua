@@ -0,0 +1,2 @@
+-- Parameterized gatekeeper: dotgk evaluate is_work_dir --var dir=$PWD
+return args.dir ~= nil and args.dir:find("/work/", 1, true) ~= nil
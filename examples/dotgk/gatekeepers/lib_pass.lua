@@ -0,0 +1,2 @@
+local hosts = require("corp.hosts")
+return hosts.is_corp_host("devvm1.corp.example.com") and #hosts.patterns == 2
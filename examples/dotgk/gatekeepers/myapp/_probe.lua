@@ -0,0 +1,2 @@
+-- Building block for composite checks; not synced on its own
+return true
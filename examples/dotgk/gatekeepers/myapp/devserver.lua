@@ -1 +1 @@
-return file_exists("src/main.rs")
+return file_exists("composite.lua")
@@ -0,0 +1,2 @@
+-- private: true
+return bool(true)
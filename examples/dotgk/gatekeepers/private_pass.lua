@@ -0,0 +1 @@
+return require("private_header") and require("myapp._probe")
@@ -0,0 +1,2 @@
+-- Explains a false result; see `dotgk get reason_fail --verbose`
+return file_exists("missing.lua") or because("missing.lua does not exist")
@@ -0,0 +1,2 @@
+-- Multivariate gatekeeper: returns a string instead of a boolean
+return os("macos") and "light" or "dark"
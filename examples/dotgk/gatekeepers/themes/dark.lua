@@ -0,0 +1,2 @@
+-- Multivariate gatekeeper loaded through dir("themes")
+return "dark"
@@ -0,0 +1,2 @@
+local theme = require("theme")
+return theme == "dark" or theme == "light"
@@ -0,0 +1,10 @@
+-- Shared helpers for corp gatekeepers; lib/ modules may return any value
+local M = {}
+
+M.patterns = { "devvm*.corp.example.com", "*.corp.example.com" }
+
+function M.is_corp_host(name)
+  return name:match("%.corp%.example%.com$") ~= nil
+end
+
+return M
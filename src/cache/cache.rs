@@ -1,4 +1,7 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fs;
 use std::path::PathBuf;
 use std::time::SystemTime;
@@ -14,11 +17,20 @@ use tracing::info;
 use tracing::instrument;
 
 use crate::cache::generators::CacheGeneratorRegistry;
-use crate::gatekeeper::load_and_evaluate_gatekeeper;
+use crate::gatekeeper::GatekeeperResult;
+use crate::gatekeeper::Variables;
+use crate::gatekeeper::base_name;
+use crate::gatekeeper::load_and_evaluate_gatekeeper_with_previous;
 use crate::gatekeeper::find_all_gatekeepers;
 use crate::gatekeeper::get_config_dir;
 use crate::gatekeeper::get_gatekeeper_path;
+use crate::gatekeeper::is_private_gatekeeper;
+use crate::gatekeeper::parse_cache_key;
+use crate::hysteresis::Hysteresis;
+use crate::hysteresis::Pending;
+use crate::hysteresis::Previous;
 use crate::settings;
+use crate::value::GatekeeperValue;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "lowercase")]
@@ -30,11 +42,54 @@ pub enum UpdateType {
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CacheEntry {
-    pub value: bool,
+    pub value: GatekeeperValue,
     pub ts: u64,
     pub update_type: UpdateType,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub expires_at: Option<u64>,
+    /// Files the gatekeeper required; modifying any of them invalidates the entry
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<PathBuf>,
+    /// Why the gatekeeper returned its value, from `reason = ...` or `because()`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Other entries the gatekeeper read with `cached()`/`is_set()`, and the values it saw
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub cache_dependencies: BTreeMap<String, Option<GatekeeperValue>>,
+    /// A new value held back by `-- debounce:`, and how many evaluations returned it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pending: Option<Pending>,
+    /// When the value last changed, tracked for `-- hold_true:`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changed_at: Option<u64>,
+}
+
+impl CacheEntry {
+    /// Entry for a result evaluated at `ts`, expiring after the result's TTL
+    fn from_result(result: GatekeeperResult, ts: u64, update_type: UpdateType) -> Self {
+        CacheEntry {
+            value: result.value,
+            ts,
+            update_type,
+            expires_at: result.ttl.map(|ttl| ts + ttl),
+            dependencies: result.dependencies,
+            reason: result.reason,
+            cache_dependencies: result.cache_dependencies,
+            pending: result.pending,
+            changed_at: result.changed_at,
+        }
+    }
+}
+
+impl From<&CacheEntry> for Previous {
+    fn from(entry: &CacheEntry) -> Self {
+        Previous {
+            value: entry.value.clone(),
+            ts: entry.ts,
+            changed_at: entry.changed_at.unwrap_or(entry.ts),
+            pending: entry.pending.clone(),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -70,6 +125,25 @@ pub fn cache_result_with_ttl(
     cache_path: Option<PathBuf>,
     update_type: UpdateType,
     ttl_seconds: Option<u64>,
+) -> Result<()> {
+    let result = GatekeeperResult {
+        value: result.into(),
+        ttl: ttl_seconds,
+        dependencies: Vec::new(),
+        reason: None,
+        cache_dependencies: BTreeMap::new(),
+        pending: None,
+        changed_at: None,
+    };
+    cache_gatekeeper_result(name, &result, cache_path, update_type)
+}
+
+/// Cache an evaluated gatekeeper result, including its reason and the files it depends on
+pub fn cache_gatekeeper_result(
+    name: &str,
+    result: &GatekeeperResult,
+    cache_path: Option<PathBuf>,
+    update_type: UpdateType,
 ) -> Result<()> {
     let cache_file_path = get_cache_path(cache_path)?;
 
@@ -97,16 +171,8 @@ pub fn cache_result_with_ttl(
         }
     };
 
-    // Calculate expiration time if TTL is provided
-    let expires_at = ttl_seconds.map(|ttl| current_timestamp + ttl);
-
     // Update the cache entry
-    let entry = CacheEntry {
-        value: result,
-        ts: current_timestamp,
-        update_type,
-        expires_at,
-    };
+    let entry = CacheEntry::from_result(result.clone(), current_timestamp, update_type);
     cache.cache.insert(name.to_string(), entry);
     cache.ts = current_timestamp;
 
@@ -118,7 +184,7 @@ pub fn cache_result_with_ttl(
 
     debug!(
         "Cached result for '{}': {} at {:?}",
-        name, result, cache_file_path
+        name, cache.cache[name].value, cache_file_path
     );
     Ok(())
 }
@@ -145,6 +211,18 @@ fn get_file_modification_time(path: &PathBuf) -> Result<u64> {
 }
 
 fn is_gatekeeper_file_modified(name: &str, cache_entry: &CacheEntry) -> bool {
+    if let Some(dependency) = cache_entry
+        .dependencies
+        .iter()
+        .find(|path| is_dependency_modified(path, cache_entry))
+    {
+        debug!(
+            "Dependency {:?} of '{}' changed since it was cached, treating as modified",
+            dependency, name
+        );
+        return true;
+    }
+
     match get_gatekeeper_path(name) {
         Ok(gatekeeper_path) => {
             if !gatekeeper_path.exists() {
@@ -186,6 +264,80 @@ fn is_gatekeeper_file_modified(name: &str, cache_entry: &CacheEntry) -> bool {
     }
 }
 
+/// Whether an entry the gatekeeper read with `cached()`/`is_set()` now has a different value
+fn is_cache_dependency_changed(
+    name: &str,
+    cache_entry: &CacheEntry,
+    entries: &HashMap<String, CacheEntry>,
+    current_timestamp: u64,
+) -> bool {
+    cache_entry.cache_dependencies.iter().any(|(dependency, seen)| {
+        let current = entries
+            .get(dependency)
+            .filter(|entry| !is_cache_entry_expired(entry, current_timestamp))
+            .map(|entry| &entry.value);
+        let changed = current != seen.as_ref();
+        if changed {
+            debug!(
+                "Cached value of '{}' read by '{}' changed since it was cached, treating as modified",
+                dependency, name
+            );
+        }
+        changed
+    })
+}
+
+/// Order gatekeepers so that entries each one read with `cached()`/`is_set()` when it
+/// was last synced are synced before it
+fn dependency_order(gatekeepers: Vec<String>, entries: &HashMap<String, CacheEntry>) -> Vec<String> {
+    fn visit(
+        name: &str,
+        gatekeepers: &HashSet<&str>,
+        entries: &HashMap<String, CacheEntry>,
+        visited: &mut HashSet<String>,
+        order: &mut Vec<String>,
+    ) {
+        if !visited.insert(name.to_string()) {
+            return;
+        }
+        if let Some(entry) = entries.get(name) {
+            for dependency in entry.cache_dependencies.keys() {
+                if gatekeepers.contains(dependency.as_str()) {
+                    visit(dependency, gatekeepers, entries, visited, order);
+                }
+            }
+        }
+        order.push(name.to_string());
+    }
+
+    let names: HashSet<&str> = gatekeepers.iter().map(String::as_str).collect();
+    let mut visited = HashSet::new();
+    let mut order = Vec::new();
+    for name in &gatekeepers {
+        visit(name, &names, entries, &mut visited, &mut order);
+    }
+    order
+}
+
+/// Whether the gatekeeper has a `-- debounce:` header. Debounce counts evaluations,
+/// so these are evaluated on every sync rather than when their entry expires
+fn is_debounced(name: &str) -> bool {
+    let debounced = get_gatekeeper_path(name)
+        .and_then(|path| Ok(fs::read_to_string(path)?))
+        .is_ok_and(|script| {
+            Hysteresis::parse(&script).is_ok_and(|hysteresis| hysteresis.debounce.is_some())
+        });
+    if debounced {
+        debug!("Gatekeeper '{}' is debounced, re-evaluating", name);
+    }
+    debounced
+}
+
+/// A dependency counts as modified if it is missing or newer than the entry
+fn is_dependency_modified(path: &PathBuf, cache_entry: &CacheEntry) -> bool {
+    get_file_modification_time(path).map_or(true, |timestamp| timestamp > cache_entry.ts)
+}
+
 #[instrument]
 pub fn set_command(
     name: String,
@@ -206,6 +358,37 @@ pub fn set_command(
     Ok(())
 }
 
+thread_local! {
+    /// Entries of the sync running on this thread, read instead of the cache file so
+    /// gatekeepers see values synced earlier in the same run
+    static SYNC_ENTRIES: RefCell<Option<HashMap<String, CacheEntry>>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with `lookup_entry` reading from `entries`, handing them back afterwards
+fn with_sync_entries<T>(entries: &mut HashMap<String, CacheEntry>, f: impl FnOnce() -> T) -> T {
+    SYNC_ENTRIES.set(Some(std::mem::take(entries)));
+    let result = f();
+    *entries = SYNC_ENTRIES.take().unwrap_or_default();
+    result
+}
+
+/// Look up a live (non-expired) entry, for gatekeepers that read other entries' values
+pub fn lookup_entry(name: &str, cache_path: Option<PathBuf>) -> Option<CacheEntry> {
+    let current_timestamp = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    let syncing = SYNC_ENTRIES.with_borrow(|entries| {
+        entries.as_ref().map(|entries| entries.get(name).cloned())
+    });
+    syncing
+        .unwrap_or_else(|| load_entry(name, cache_path))
+        .filter(|entry| !is_cache_entry_expired(entry, current_timestamp))
+}
+
+/// Look up an entry whether or not it has expired
+pub fn load_entry(name: &str, cache_path: Option<PathBuf>) -> Option<CacheEntry> {
+    let cache_file_path = get_cache_path(cache_path).ok()?;
+    load_cache(&cache_file_path)?.cache.remove(name)
+}
+
 fn load_cache(cache_file_path: &PathBuf) -> Option<Cache> {
     if cache_file_path.exists() {
         let cache_content = fs::read_to_string(cache_file_path)
@@ -233,7 +416,7 @@ fn write_cache(cache: &Cache, cache_file_path: &PathBuf) -> Result<()> {
 }
 
 #[instrument]
-fn get_all_gatekeepers(cache_path: Option<PathBuf>) -> Result<()> {
+fn get_all_gatekeepers(cache_path: Option<PathBuf>, verbose: bool) -> Result<()> {
     info!("Getting all cached gatekeeper values");
 
     let cache_file_path = get_cache_path(cache_path)?;
@@ -242,24 +425,27 @@ fn get_all_gatekeepers(cache_path: Option<PathBuf>) -> Result<()> {
     let existing_cache = load_cache(&cache_file_path);
 
     if let Some(cache) = existing_cache {
-        if cache.cache.is_empty() {
+        // Collect and sort results by name for consistent output, hiding private gatekeepers
+        let mut results: Vec<(&String, &CacheEntry)> = cache
+            .cache
+            .iter()
+            .filter(|(name, _)| !is_private_gatekeeper(name))
+            .collect();
+
+        if results.is_empty() {
             println!("No cached gatekeepers found");
             return Ok(());
         }
 
-        info!("Found {} cached gatekeepers", cache.cache.len());
+        info!("Found {} cached gatekeepers", results.len());
 
-        // Collect and sort results by name for consistent output
-        let mut results: Vec<(String, bool)> = cache
-            .cache
-            .iter()
-            .map(|(name, entry)| (name.clone(), entry.value))
-            .collect();
-
-        results.sort_by(|a, b| a.0.cmp(&b.0));
+        results.sort_by(|a, b| a.0.cmp(b.0));
 
-        for (name, value) in results {
-            println!("{}: {}", name, value);
+        for (name, entry) in results {
+            match &entry.reason {
+                Some(reason) if verbose => println!("{}: {} ({})", name, entry.value, reason),
+                _ => println!("{}: {}", name, entry.value),
+            }
         }
     } else {
         println!("No cache file found");
@@ -269,15 +455,15 @@ fn get_all_gatekeepers(cache_path: Option<PathBuf>) -> Result<()> {
 }
 
 #[instrument]
-pub fn get_command(name: Option<String>, cache_path: Option<PathBuf>) -> Result<()> {
+pub fn get_command(name: Option<String>, cache_path: Option<PathBuf>, verbose: bool) -> Result<()> {
     match name {
-        Some(name) => get_single_gatekeeper(name, cache_path),
-        None => get_all_gatekeepers(cache_path),
+        Some(name) => get_single_gatekeeper(name, cache_path, verbose),
+        None => get_all_gatekeepers(cache_path, verbose),
     }
 }
 
 #[instrument]
-fn get_single_gatekeeper(name: String, cache_path: Option<PathBuf>) -> Result<()> {
+fn get_single_gatekeeper(name: String, cache_path: Option<PathBuf>, verbose: bool) -> Result<()> {
     info!("Getting cached gatekeeper value: {}", name);
 
     let cache_file_path = get_cache_path(cache_path)?;
@@ -285,12 +471,15 @@ fn get_single_gatekeeper(name: String, cache_path: Option<PathBuf>) -> Result<()
     // Load existing cache
     let existing_cache = load_cache(&cache_file_path);
 
-    if let Some(cache) = existing_cache {
-        if let Some(entry) = cache.cache.get(&name) {
-            info!("Found cache entry for '{}': {}", name, entry.value);
-            println!("{}", entry.value);
-            return Ok(());
+    if let Some(cache) = existing_cache
+        && let Some(entry) = cache.cache.get(&name)
+    {
+        info!("Found cache entry for '{}': {}", name, entry.value);
+        println!("{}", entry.value);
+        if verbose && let Some(reason) = &entry.reason {
+            println!("reason: {}", reason);
         }
+        return Ok(());
     }
 
     // No cache entry found
@@ -392,6 +581,10 @@ pub fn sync_command(cache_path: Option<PathBuf>, force: bool) -> Result<()> {
 
     let mut removed_count = 0;
 
+    // Entries evaluated outside of sync, with variables (`name{dir=/a}`) or for private
+    // gatekeepers; checked like their gatekeeper once the gatekeepers are synced
+    let mut evaluated_entries = Vec::new();
+
     // First, preserve non-expired entries that aren't gatekeepers
     // Also remove old gatekeeper entries that no longer have files (unless they were set manually)
     for (name, entry) in existing_cache.cache.iter() {
@@ -401,8 +594,9 @@ pub fn sync_command(cache_path: Option<PathBuf>, force: bool) -> Result<()> {
                 let should_remove = match entry.update_type {
                     UpdateType::Set => false, // Never remove manually set entries
                     UpdateType::Evaluate | UpdateType::Sync => {
-                        // Remove if no corresponding gatekeeper file exists
-                        match get_gatekeeper_path(name) {
+                        // Remove if no corresponding gatekeeper file exists, ignoring
+                        // variables in keys like `name{dir=/a}`
+                        match get_gatekeeper_path(base_name(name)) {
                             Ok(gatekeeper_path) => !gatekeeper_path.exists(),
                             Err(_) => true, // Remove if we can't determine the path
                         }
@@ -415,6 +609,8 @@ pub fn sync_command(cache_path: Option<PathBuf>, force: bool) -> Result<()> {
                         name
                     );
                     removed_count += 1;
+                } else if !matches!(entry.update_type, UpdateType::Set) {
+                    evaluated_entries.push(name.clone());
                 } else {
                     cache_entries.insert(name.clone(), entry.clone());
                     preserved_count += 1;
@@ -426,33 +622,40 @@ pub fn sync_command(cache_path: Option<PathBuf>, force: bool) -> Result<()> {
         }
     }
 
-    // Process gatekeepers
-    for name in gatekeepers {
+    // What `cached()` sees during the sync: entries kept so far, with each gatekeeper's
+    // entry replaced as soon as it is synced
+    let mut sync_entries = cache_entries.clone();
+    for name in gatekeepers.iter().chain(&evaluated_entries) {
+        if let Some(entry) = existing_cache.cache.get(name) {
+            sync_entries.insert(name.clone(), entry.clone());
+        }
+    }
+
+    // Process gatekeepers, the entries they read first
+    for name in dependency_order(gatekeepers, &existing_cache.cache) {
         let existing_entry = existing_cache.cache.get(&name);
         let should_evaluate = force
             || version_mismatch
             || settings_modified
             || existing_entry.is_none()
-            || existing_entry.map_or(false, |entry| {
+            || is_debounced(&name)
+            || existing_entry.is_some_and(|entry| {
                 is_cache_entry_expired(entry, current_timestamp)
                     || is_gatekeeper_file_modified(&name, entry)
+                    || is_cache_dependency_changed(&name, entry, &sync_entries, current_timestamp)
             });
 
         if should_evaluate {
             info!("Evaluating gatekeeper: {}", name);
-            let gatekeeper_result = load_and_evaluate_gatekeeper(&name)?;
-            let result = gatekeeper_result.value;
-            let expires_at = gatekeeper_result.ttl.map(|ttl| current_timestamp + ttl);
-
-            let entry = CacheEntry {
-                value: result,
-                ts: current_timestamp,
-                update_type: UpdateType::Sync,
-                expires_at,
-            };
+            let gatekeeper_result = with_sync_entries(&mut sync_entries, || {
+                load_and_evaluate_gatekeeper_with_previous(&name, existing_entry, &Variables::new())
+            })?;
+            info!("Cached result for '{}': {}", name, gatekeeper_result.value);
+            let entry =
+                CacheEntry::from_result(gatekeeper_result, current_timestamp, UpdateType::Sync);
+            sync_entries.insert(name.clone(), entry.clone());
             cache_entries.insert(name.clone(), entry);
             updated_count += 1;
-            info!("Cached result for '{}': {}", name, result);
         } else {
             // Keep existing entry
             if let Some(entry) = existing_entry {
@@ -463,6 +666,39 @@ pub fn sync_command(cache_path: Option<PathBuf>, force: bool) -> Result<()> {
         }
     }
 
+    // Re-evaluate entries from explicit evaluations when their gatekeeper, a file it
+    // required or an entry it read changed, with the variables recorded in the key
+    for key in evaluated_entries {
+        let entry = &existing_cache.cache[&key];
+        let Some((name, vars)) = parse_cache_key(&key) else {
+            info!("Removing entry '{}' with unreadable variables", key);
+            removed_count += 1;
+            continue;
+        };
+
+        if is_gatekeeper_file_modified(name, entry)
+            || is_cache_dependency_changed(&key, entry, &sync_entries, current_timestamp)
+        {
+            info!("Evaluating gatekeeper: {}", key);
+            let gatekeeper_result = with_sync_entries(&mut sync_entries, || {
+                load_and_evaluate_gatekeeper_with_previous(name, Some(entry), &vars)
+            })?;
+            info!("Cached result for '{}': {}", key, gatekeeper_result.value);
+            let entry = CacheEntry::from_result(
+                gatekeeper_result,
+                current_timestamp,
+                entry.update_type.clone(),
+            );
+            sync_entries.insert(key.clone(), entry.clone());
+            cache_entries.insert(key, entry);
+            updated_count += 1;
+        } else {
+            cache_entries.insert(key.clone(), entry.clone());
+            preserved_count += 1;
+            debug!("Preserved non-expired entry for '{}'", key);
+        }
+    }
+
     let cache = Cache {
         cache: cache_entries,
         ts: current_timestamp,
@@ -601,11 +837,11 @@ pub fn rm_command(name: String, cache_path: Option<PathBuf>, remove_file: bool)
     }
 
     // Write updated cache if it was modified
-    if cache_updated {
-        if let Err(e) = write_cache(&cache, &cache_file_path) {
-            error!("Failed to update cache: {}", e);
-            return Err(e);
-        }
+    if cache_updated
+        && let Err(e) = write_cache(&cache, &cache_file_path)
+    {
+        error!("Failed to update cache: {}", e);
+        return Err(e);
     }
 
     // Provide user feedback
@@ -640,10 +876,15 @@ mod tests {
 
     fn create_test_cache_entry(ts: u64, expires_at: Option<u64>) -> CacheEntry {
         CacheEntry {
-            value: true,
+            value: true.into(),
             ts,
             update_type: UpdateType::Evaluate,
             expires_at,
+            dependencies: Vec::new(),
+            reason: None,
+            cache_dependencies: BTreeMap::new(),
+            pending: None,
+            changed_at: None,
         }
     }
 
@@ -760,6 +1001,54 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_is_gatekeeper_file_modified_dependency_changed() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dependency = temp_dir.path().join("hosts.lua");
+        fs::write(&dependency, "return {}")?;
+
+        // Cached after both the gatekeeper and its dependency were written
+        let future = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() + 10;
+        let mut cache_entry = create_test_cache_entry(future, None);
+        cache_entry.dependencies = vec![dependency.clone()];
+        assert!(!is_gatekeeper_file_modified("bool_pass", &cache_entry));
+
+        // Cached before the dependency changed
+        cache_entry.ts = future - 20;
+        assert!(is_dependency_modified(&dependency, &cache_entry));
+
+        // A dependency that was removed also invalidates the entry
+        cache_entry.ts = future;
+        fs::remove_file(&dependency)?;
+        assert!(is_gatekeeper_file_modified("bool_pass", &cache_entry));
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_cache_dependency_changed() {
+        let current_timestamp = 1000;
+        let mut entries = HashMap::new();
+        entries.insert("manual/work_mode".to_string(), create_test_cache_entry(500, None));
+        entries.insert("expired".to_string(), create_test_cache_entry(500, Some(900)));
+
+        let mut entry = create_test_cache_entry(800, None);
+        entry.cache_dependencies = BTreeMap::from([
+            ("manual/work_mode".to_string(), Some(true.into())),
+            ("expired".to_string(), None),
+            ("never_set".to_string(), None),
+        ]);
+        assert!(!is_cache_dependency_changed("reader", &entry, &entries, current_timestamp));
+
+        // Set by hand to a different value
+        entries.get_mut("manual/work_mode").unwrap().value = false.into();
+        assert!(is_cache_dependency_changed("reader", &entry, &entries, current_timestamp));
+        entries.get_mut("manual/work_mode").unwrap().value = true.into();
+
+        // Removed with `dotgk rm`
+        entries.remove("manual/work_mode");
+        assert!(is_cache_dependency_changed("reader", &entry, &entries, current_timestamp));
+    }
+
     #[test]
     fn test_cache_result_with_ttl_new_cache() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -885,6 +1174,39 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_cache_gatekeeper_result_keeps_reason() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let cache_path = temp_dir.path().join("cache.json");
+
+        let result = GatekeeperResult {
+            value: false.into(),
+            ttl: None,
+            dependencies: Vec::new(),
+            reason: Some("not on corp VPN".to_string()),
+            cache_dependencies: BTreeMap::new(),
+            pending: None,
+            changed_at: None,
+        };
+        cache_gatekeeper_result(
+            "with_reason",
+            &result,
+            Some(cache_path.clone()),
+            UpdateType::Evaluate,
+        )?;
+        cache_result_with_ttl("without_reason", true, Some(cache_path.clone()), UpdateType::Set, None)?;
+
+        let cache_content = fs::read_to_string(&cache_path)?;
+        let cache: Cache = serde_json::from_str(&cache_content)?;
+
+        assert_eq!(cache.cache["with_reason"].reason.as_deref(), Some("not on corp VPN"));
+        assert_eq!(cache.cache["without_reason"].reason, None);
+        // Entries without a reason don't write the field at all
+        assert_eq!(cache_content.matches("\"reason\"").count(), 1);
+
+        Ok(())
+    }
+
     #[test]
     fn test_update_type_serialization() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -940,4 +1262,130 @@ mod tests {
         assert!(is_modified);
         Ok(())
     }
+
+    #[test]
+    fn test_sync_confirms_debounced_value() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let cache_path = temp_dir.path().join("dotgk.json");
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+        // Cached as false without a TTL, while the gatekeeper now returns true
+        let mut entry = create_test_cache_entry(now, None);
+        entry.value = false.into();
+        let cache = Cache {
+            cache: HashMap::from([("debounce_pass".to_string(), entry)]),
+            ts: now,
+            version: get_current_version(),
+        };
+        write_cache(&cache, &cache_path)?;
+
+        // Each sync counts one evaluation; the third confirms the new value
+        for count in 1..=2 {
+            sync_command(Some(cache_path.clone()), false)?;
+            let entry = load_entry("debounce_pass", Some(cache_path.clone())).unwrap();
+            assert_eq!(entry.value, false);
+            assert_eq!(entry.pending.map(|pending| pending.count), Some(count));
+        }
+        sync_command(Some(cache_path.clone()), false)?;
+        let entry = load_entry("debounce_pass", Some(cache_path.clone())).unwrap();
+        assert_eq!(entry.value, true);
+        assert_eq!(entry.pending, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sync_evaluates_cache_dependencies_first() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let cache_path = temp_dir.path().join("dotgk.json");
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+        // cache_source has expired and now returns true; cache_mirror last saw it false
+        let mut source = create_test_cache_entry(now - 10, Some(now - 1));
+        source.value = false.into();
+        let mut mirror = create_test_cache_entry(now, None);
+        mirror.value = false.into();
+        mirror
+            .cache_dependencies
+            .insert("cache_source".to_string(), Some(false.into()));
+        let cache = Cache {
+            cache: HashMap::from([
+                ("cache_source".to_string(), source),
+                ("cache_mirror".to_string(), mirror),
+            ]),
+            ts: now,
+            version: get_current_version(),
+        };
+        write_cache(&cache, &cache_path)?;
+
+        // Both change in the same sync
+        sync_command(Some(cache_path.clone()), false)?;
+        let source = load_entry("cache_source", Some(cache_path.clone())).unwrap();
+        let mirror = load_entry("cache_mirror", Some(cache_path.clone())).unwrap();
+        assert_eq!(source.value, true);
+        assert_eq!(mirror.value, true);
+        assert_eq!(
+            mirror.cache_dependencies.get("cache_source"),
+            Some(&Some(true.into()))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_dependency_order() {
+        let mut entries = HashMap::new();
+        let mut mirror = create_test_cache_entry(1000, None);
+        mirror.cache_dependencies.insert("source".to_string(), None);
+        mirror.cache_dependencies.insert("manual".to_string(), None);
+        entries.insert("mirror".to_string(), mirror);
+
+        let order = dependency_order(
+            vec!["mirror".to_string(), "other".to_string(), "source".to_string()],
+            &entries,
+        );
+        assert_eq!(order, vec!["source", "mirror", "other"]);
+    }
+
+    #[test]
+    fn test_sync_checks_evaluated_entries() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let cache_path = temp_dir.path().join("dotgk.json");
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+        // Cached before the gatekeeper files were last written, so they count as modified
+        let stale = |value: bool| {
+            let mut entry = create_test_cache_entry(0, None);
+            entry.value = value.into();
+            entry
+        };
+        // Cached after, so kept as is even though they'd now evaluate differently
+        let fresh = |value: bool| {
+            let mut entry = create_test_cache_entry(now, None);
+            entry.value = value.into();
+            entry
+        };
+        let cache = Cache {
+            cache: HashMap::from([
+                ("is_work_dir{dir=/srv/work/app}".to_string(), stale(false)),
+                ("is_work_dir{dir=/srv/work/api}".to_string(), fresh(false)),
+                ("myapp/_probe".to_string(), stale(false)),
+                ("private_header".to_string(), fresh(false)),
+                ("is_work_dir{dir}".to_string(), stale(false)),
+            ]),
+            ts: now,
+            version: get_current_version(),
+        };
+        write_cache(&cache, &cache_path)?;
+
+        sync_command(Some(cache_path.clone()), false)?;
+        let value = |key: &str| load_entry(key, Some(cache_path.clone())).map(|entry| entry.value);
+        // Re-evaluated with the variables from the key
+        assert_eq!(value("is_work_dir{dir=/srv/work/app}"), Some(true.into()));
+        assert_eq!(value("is_work_dir{dir=/srv/work/api}"), Some(false.into()));
+        // Private gatekeepers that were evaluated explicitly are checked the same way
+        assert_eq!(value("myapp/_probe"), Some(true.into()));
+        assert_eq!(value("private_header"), Some(false.into()));
+        // Keys that don't parse can't be re-evaluated
+        assert_eq!(value("is_work_dir{dir}"), None);
+        Ok(())
+    }
 }
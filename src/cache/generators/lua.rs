@@ -3,6 +3,8 @@ use anyhow::Result;
 use crate::cache::cache::Cache;
 use crate::cache::cache::CacheEntry;
 use crate::cache::generators::CacheGenerator;
+use crate::cache::generators::reason_comment;
+use crate::value::GatekeeperValue;
 
 /// Lua cache generator
 pub struct LuaCacheGenerator;
@@ -27,7 +29,11 @@ impl CacheGenerator for LuaCacheGenerator {
         entries.sort_by(|a, b| a.0.cmp(b.0));
 
         for (name, entry) in entries {
-            content.push_str(&format!("  [\"{}\"] = {},\n", name, entry.value));
+            content.push_str(&format!("  [{}] = {},", lua_string(name), lua_literal(&entry.value)));
+            if let Some(reason) = reason_comment(entry) {
+                content.push_str(&format!(" -- {}", reason));
+            }
+            content.push('\n');
         }
 
         content.push_str("}\n\n");
@@ -35,7 +41,16 @@ impl CacheGenerator for LuaCacheGenerator {
         // Add helper function
         content.push_str("-- Helper function to check gatekeeper values\n");
         content.push_str("function dotgk.check(name)\n");
-        content.push_str("  return dotgk[name] or false\n");
+        content.push_str("  return dotgk[name] == true\n");
+        content.push_str("end\n\n");
+
+        content.push_str("-- Helper function to get string and number values, or a default if missing\n");
+        content.push_str("function dotgk.value(name, default)\n");
+        content.push_str("  local value = dotgk[name]\n");
+        content.push_str("  if value == nil then\n");
+        content.push_str("    return default\n");
+        content.push_str("  end\n");
+        content.push_str("  return value\n");
         content.push_str("end\n\n");
 
         content.push_str("return dotgk\n");
@@ -44,10 +59,46 @@ impl CacheGenerator for LuaCacheGenerator {
     }
 }
 
+/// Render a value as a Lua literal
+fn lua_literal(value: &GatekeeperValue) -> String {
+    match value {
+        GatekeeperValue::Bool(value) => value.to_string(),
+        GatekeeperValue::Integer(value) => value.to_string(),
+        // Debug formatting keeps a decimal point, so 2.0 stays a float
+        GatekeeperValue::Number(value) => format!("{:?}", value),
+        GatekeeperValue::String(value) => lua_string(value),
+    }
+}
+
+/// Render text as a double-quoted Lua string
+fn lua_string(text: &str) -> String {
+    let mut literal = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '\\' => literal.push_str("\\\\"),
+            '"' => literal.push_str("\\\""),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            // Lua escapes are bytes, so C1 controls take one per UTF-8 byte
+            c if c.is_control() => {
+                for byte in c.encode_utf8(&mut [0; 4]).bytes() {
+                    literal.push_str(&format!("\\{:03}", byte));
+                }
+            }
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::cache::generators::test_utils::create_test_cache;
+    use crate::cache::generators::test_utils::create_test_cache_with_unsafe_name;
+    use crate::cache::generators::test_utils::create_test_cache_with_values;
 
     #[test]
     fn test_lua_generator() -> Result<()> {
@@ -62,7 +113,44 @@ mod tests {
         assert!(content.contains("[\"test-gk\"] = true"));
         assert!(content.contains("function dotgk.check(name)"));
         assert!(content.contains("return dotgk"));
+        assert!(content.contains("function dotgk.value(name, default)"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lua_generator_typed_values() -> Result<()> {
+        let generator = LuaCacheGenerator;
+        let cache = create_test_cache_with_values();
+
+        let content = generator.generate_content(&cache)?;
+
+        assert!(content.contains("[\"theme\"] = \"dark\", -- dark mode after sunset\n"));
+        assert!(content.contains("[\"jobs\"] = 4,"));
+        assert!(content.contains("[\"ratio\"] = 0.5,"));
+        assert!(content.contains(r#"["quoted"] = "say \"hi\" to $USER `now` \\o/","#));
 
         Ok(())
     }
+
+    #[test]
+    fn test_lua_generator_escapes_names() -> Result<()> {
+        let generator = LuaCacheGenerator;
+        let cache = create_test_cache_with_unsafe_name();
+
+        let content = generator.generate_content(&cache)?;
+
+        assert!(content.contains(r#"  ["is_work_dir{dir=/tmp/\"x\"$(touch pwned)}"] = true,"#));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lua_literal_escapes_control_characters() {
+        let value = GatekeeperValue::String("a\nb\u{1}2".to_string());
+        assert_eq!(lua_literal(&value), r#""a\nb\0012""#);
+        let value = GatekeeperValue::String("a\u{85}b".to_string());
+        assert_eq!(lua_literal(&value), r#""a\194\133b""#);
+        assert_eq!(lua_literal(&GatekeeperValue::Number(2.0)), "2.0");
+    }
 }
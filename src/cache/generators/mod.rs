@@ -7,7 +7,9 @@ use anyhow::Result;
 use tracing::info;
 
 use crate::cache::cache::Cache;
+use crate::cache::cache::CacheEntry;
 use crate::gatekeeper::get_config_dir;
+use crate::gatekeeper::is_private_gatekeeper;
 
 pub mod lua;
 pub mod python;
@@ -54,6 +56,12 @@ pub trait CacheGenerator {
     }
 }
 
+/// An entry's reason flattened to a single line, for use in a trailing comment
+pub fn reason_comment(entry: &CacheEntry) -> Option<String> {
+    let reason = entry.reason.as_deref()?;
+    Some(reason.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
 /// Registry for managing cache generators
 pub struct CacheGeneratorRegistry {
     generators: HashMap<String, Box<dyn CacheGenerator>>,
@@ -91,8 +99,9 @@ impl CacheGeneratorRegistry {
         self.generators.keys().map(|s| s.as_str()).collect()
     }
 
-    /// Generate cache files for the specified formats
+    /// Generate cache files for the specified formats, leaving out private gatekeepers
     pub fn generate_caches(&self, cache: &Cache, enabled_formats: &[String]) -> Vec<String> {
+        let cache = &Self::public_entries(cache);
         let mut generated_formats = Vec::new();
 
         for format in enabled_formats {
@@ -109,6 +118,20 @@ impl CacheGeneratorRegistry {
 
         generated_formats
     }
+
+    /// The cache without entries for private gatekeepers
+    fn public_entries(cache: &Cache) -> Cache {
+        Cache {
+            cache: cache
+                .cache
+                .iter()
+                .filter(|(name, _)| !is_private_gatekeeper(name))
+                .map(|(name, entry)| (name.clone(), entry.clone()))
+                .collect(),
+            ts: cache.ts,
+            version: cache.version.clone(),
+        }
+    }
 }
 
 impl Default for CacheGeneratorRegistry {
@@ -135,4 +158,18 @@ mod tests {
         assert!(available.contains(&"shell"));
         assert!(available.contains(&"python"));
     }
+
+    #[test]
+    fn test_public_entries_skip_private_gatekeepers() {
+        let mut cache = test_utils::create_test_cache();
+        let entry = cache.cache["test-gk"].clone();
+        cache.cache.insert("work/_vpn_probe".to_string(), entry.clone());
+        cache.cache.insert("private_header".to_string(), entry);
+
+        let public = CacheGeneratorRegistry::public_entries(&cache);
+        assert!(public.cache.contains_key("test-gk"));
+        assert!(public.cache.contains_key("another_gk"));
+        assert!(!public.cache.contains_key("work/_vpn_probe"));
+        assert!(!public.cache.contains_key("private_header"));
+    }
 }
@@ -3,6 +3,8 @@ use anyhow::Result;
 use crate::cache::cache::Cache;
 use crate::cache::cache::CacheEntry;
 use crate::cache::generators::CacheGenerator;
+use crate::cache::generators::reason_comment;
+use crate::value::GatekeeperValue;
 
 /// Python cache generator
 pub struct PythonCacheGenerator;
@@ -29,24 +31,50 @@ impl CacheGenerator for PythonCacheGenerator {
         // Generate dictionary
         content.push_str("DOTGK = {\n");
         for (name, entry) in entries {
-            let value = if entry.value { "True" } else { "False" };
-            content.push_str(&format!("    \"{}\": {},\n", name, value));
+            content.push_str(&format!(
+                "    {}: {},",
+                serde_json::to_string(name)?,
+                python_literal(&entry.value)?
+            ));
+            if let Some(reason) = reason_comment(entry) {
+                content.push_str(&format!("  # {}", reason));
+            }
+            content.push('\n');
         }
         content.push_str("}\n\n");
 
         // Add helper function
         content.push_str("def check(name: str) -> bool:\n");
         content.push_str("    \"\"\"Check if a gatekeeper is enabled.\"\"\"\n");
-        content.push_str("    return DOTGK.get(name, False)\n");
+        content.push_str("    return DOTGK.get(name) is True\n\n\n");
+
+        content.push_str("def value(name: str, default=None):\n");
+        content.push_str("    \"\"\"Get a gatekeeper's value, or a default if it is missing.\"\"\"\n");
+        content.push_str("    return DOTGK.get(name, default)\n");
 
         Ok(content)
     }
 }
 
+/// Render a value as a Python literal
+fn python_literal(value: &GatekeeperValue) -> Result<String> {
+    Ok(match value {
+        GatekeeperValue::Bool(true) => "True".to_string(),
+        GatekeeperValue::Bool(false) => "False".to_string(),
+        GatekeeperValue::Integer(value) => value.to_string(),
+        // Debug formatting keeps a decimal point, so 2.0 stays a float
+        GatekeeperValue::Number(value) => format!("{:?}", value),
+        // JSON string escapes are valid Python string escapes
+        GatekeeperValue::String(value) => serde_json::to_string(value)?,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::cache::generators::test_utils::create_test_cache;
+    use crate::cache::generators::test_utils::create_test_cache_with_unsafe_name;
+    use crate::cache::generators::test_utils::create_test_cache_with_values;
 
     #[test]
     fn test_python_generator() -> Result<()> {
@@ -61,6 +89,34 @@ mod tests {
         assert!(content.contains("    \"another_gk\": False,"));
         assert!(content.contains("    \"test-gk\": True,"));
         assert!(content.contains("def check(name: str) -> bool:"));
+        assert!(content.contains("def value(name: str, default=None):"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_python_generator_typed_values() -> Result<()> {
+        let generator = PythonCacheGenerator;
+        let cache = create_test_cache_with_values();
+
+        let content = generator.generate_content(&cache)?;
+
+        assert!(content.contains("    \"theme\": \"dark\",  # dark mode after sunset\n"));
+        assert!(content.contains("    \"jobs\": 4,"));
+        assert!(content.contains("    \"ratio\": 0.5,"));
+        assert!(content.contains(r#"    "quoted": "say \"hi\" to $USER `now` \\o/","#));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_python_generator_escapes_names() -> Result<()> {
+        let generator = PythonCacheGenerator;
+        let cache = create_test_cache_with_unsafe_name();
+
+        let content = generator.generate_content(&cache)?;
+
+        assert!(content.contains(r#"    "is_work_dir{dir=/tmp/\"x\"$(touch pwned)}": True,"#));
 
         Ok(())
     }
@@ -3,6 +3,7 @@ use anyhow::Result;
 use crate::cache::cache::Cache;
 use crate::cache::cache::CacheEntry;
 use crate::cache::generators::CacheGenerator;
+use crate::cache::generators::reason_comment;
 
 /// Shell cache generator
 pub struct ShellCacheGenerator;
@@ -31,8 +32,15 @@ impl CacheGenerator for ShellCacheGenerator {
         content.push_str("# Gatekeeper values stored in associative array\n");
         content.push_str("declare -A _DOTGK_VALUES=(\n");
         for (name, entry) in entries {
-            let value = if entry.value { "true" } else { "false" };
-            content.push_str(&format!("  [\"{}\"]=\"{}\"\n", name, value));
+            content.push_str(&format!(
+                "  [\"{}\"]=\"{}\"",
+                shell_escape(name),
+                shell_escape(&entry.value.to_string())
+            ));
+            if let Some(reason) = reason_comment(entry) {
+                content.push_str(&format!("  # {}", reason));
+            }
+            content.push('\n');
         }
         content.push_str(")\n\n");
 
@@ -42,16 +50,37 @@ impl CacheGenerator for ShellCacheGenerator {
         content.push_str("  local name=\"$1\"\n");
         content.push_str("  local value=\"${_DOTGK_VALUES[$name]:-false}\"\n");
         content.push_str("  [[ \"$value\" == \"true\" ]]\n");
+        content.push_str("}\n\n");
+
+        // Add a helper function to print string and number values
+        content.push_str("# Helper function to print a gatekeeper's value, or a default if missing\n");
+        content.push_str("dotgk_value() {\n");
+        content.push_str("  local name=\"$1\"\n");
+        content.push_str("  printf '%s\\n' \"${_DOTGK_VALUES[$name]-$2}\"\n");
         content.push_str("}\n");
 
         Ok(content)
     }
 }
 
+/// Escape a name or value for use inside a double-quoted shell string
+fn shell_escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        if matches!(c, '\\' | '"' | '$' | '`') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::cache::generators::test_utils::create_test_cache;
+    use crate::cache::generators::test_utils::create_test_cache_with_unsafe_name;
+    use crate::cache::generators::test_utils::create_test_cache_with_values;
 
     #[test]
     fn test_shell_generator() -> Result<()> {
@@ -66,6 +95,34 @@ mod tests {
         assert!(content.contains("[\"another_gk\"]=\"false\""));
         assert!(content.contains("[\"test-gk\"]=\"true\""));
         assert!(content.contains("dotgk_check() {"));
+        assert!(content.contains("dotgk_value() {"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_shell_generator_typed_values() -> Result<()> {
+        let generator = ShellCacheGenerator;
+        let cache = create_test_cache_with_values();
+
+        let content = generator.generate_content(&cache)?;
+
+        assert!(content.contains("[\"theme\"]=\"dark\"  # dark mode after sunset\n"));
+        assert!(content.contains("[\"jobs\"]=\"4\""));
+        assert!(content.contains("[\"ratio\"]=\"0.5\""));
+        assert!(content.contains(r#"["quoted"]="say \"hi\" to \$USER \`now\` \\o/""#));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_shell_generator_escapes_names() -> Result<()> {
+        let generator = ShellCacheGenerator;
+        let cache = create_test_cache_with_unsafe_name();
+
+        let content = generator.generate_content(&cache)?;
+
+        assert!(content.contains(r#"  ["is_work_dir{dir=/tmp/\"x\"\$(touch pwned)}"]="true""#));
 
         Ok(())
     }
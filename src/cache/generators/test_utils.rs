@@ -1,4 +1,6 @@
 #[cfg(test)]
+use std::collections::BTreeMap;
+#[cfg(test)]
 use std::collections::HashMap;
 
 #[cfg(test)]
@@ -7,6 +9,8 @@ use crate::cache::cache::Cache;
 use crate::cache::cache::CacheEntry;
 #[cfg(test)]
 use crate::cache::cache::UpdateType;
+#[cfg(test)]
+use crate::value::GatekeeperValue;
 
 #[cfg(test)]
 pub fn create_test_cache() -> Cache {
@@ -14,19 +18,29 @@ pub fn create_test_cache() -> Cache {
     cache_entries.insert(
         "test-gk".to_string(),
         CacheEntry {
-            value: true,
+            value: true.into(),
             ts: 1000,
             update_type: UpdateType::Sync,
             expires_at: None,
+            dependencies: Vec::new(),
+            reason: None,
+            cache_dependencies: BTreeMap::new(),
+            pending: None,
+            changed_at: None,
         },
     );
     cache_entries.insert(
         "another_gk".to_string(),
         CacheEntry {
-            value: false,
+            value: false.into(),
             ts: 1000,
             update_type: UpdateType::Sync,
             expires_at: None,
+            dependencies: Vec::new(),
+            reason: None,
+            cache_dependencies: BTreeMap::new(),
+            pending: None,
+            changed_at: None,
         },
     );
 
@@ -36,3 +50,53 @@ pub fn create_test_cache() -> Cache {
         version: "0.1.0".to_string(),
     }
 }
+
+/// A cache holding string and number values, including one that needs escaping
+/// and one with a reason
+#[cfg(test)]
+pub fn create_test_cache_with_values() -> Cache {
+    let values = [
+        ("theme", GatekeeperValue::String("dark".to_string())),
+        ("jobs", GatekeeperValue::Integer(4)),
+        ("ratio", GatekeeperValue::Number(0.5)),
+        ("quoted", GatekeeperValue::String(r#"say "hi" to $USER `now` \o/"#.to_string())),
+    ];
+    let cache_entries = values
+        .into_iter()
+        .map(|(name, value)| {
+            let entry = CacheEntry {
+                value,
+                ts: 1000,
+                update_type: UpdateType::Sync,
+                expires_at: None,
+                dependencies: Vec::new(),
+                // A multi-line reason, which generators must keep on one comment line
+                reason: (name == "theme").then(|| "dark mode after\nsunset".to_string()),
+                cache_dependencies: BTreeMap::new(),
+                pending: None,
+                changed_at: None,
+            };
+            (name.to_string(), entry)
+        })
+        .collect();
+
+    Cache {
+        cache: cache_entries,
+        ts: 1000,
+        version: "0.1.0".to_string(),
+    }
+}
+
+/// Name of a variable-keyed entry whose `--var` value tries to break out of a quoted string
+#[cfg(test)]
+pub const UNSAFE_NAME: &str = r#"is_work_dir{dir=/tmp/"x"$(touch pwned)}"#;
+
+/// A cache with a single true entry stored under `UNSAFE_NAME`
+#[cfg(test)]
+pub fn create_test_cache_with_unsafe_name() -> Cache {
+    let mut cache = create_test_cache();
+    let entry = cache.cache.remove("test-gk").unwrap();
+    cache.cache.clear();
+    cache.cache.insert(UNSAFE_NAME.to_string(), entry);
+    cache
+}
@@ -15,11 +15,20 @@ pub enum Command {
         /// Disable caching of the evaluation result
         #[clap(long)]
         no_cache: bool,
+        /// Variable exposed to the gatekeeper in its `args` table (repeatable)
+        #[clap(long = "var", value_name = "KEY=VALUE", value_parser = parse_var)]
+        vars: Vec<(String, String)>,
+        /// Show log.debug/info/warn output from the gatekeeper on stderr
+        #[clap(long)]
+        verbose: bool,
     },
     /// Get a gatekeeper value from cache, evaluating if expired or missing
     Get {
         /// Gatekeeper name (if not provided, shows all gatekeepers)
         name: Option<String>,
+        /// Also show why each gatekeeper returned its value
+        #[clap(long)]
+        verbose: bool,
     },
     /// Set a value in the cache
     Set {
@@ -50,6 +59,24 @@ pub enum Command {
     },
 }
 
+/// Parse a `--var key=value` argument
+fn parse_var(var: &str) -> Result<(String, String), String> {
+    let (key, value) = var
+        .split_once('=')
+        .ok_or_else(|| format!("expected KEY=VALUE, got '{}'", var))?;
+    let valid = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid {
+        return Err(format!(
+            "invalid variable name '{}', use letters, digits, '_' or '-'",
+            key
+        ));
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
 #[derive(clap::Subcommand, Debug)]
 pub enum CacheAction {
     /// Enable a cache format
@@ -0,0 +1,265 @@
+use std::fmt;
+use std::sync::LazyLock;
+
+use mlua::prelude::LuaError;
+use regex::Regex;
+
+/// Lines of source shown before and after the failing line
+const CONTEXT_LINES: usize = 2;
+
+/// `chunk:line: message`, the prefix Lua puts on errors raised in scripts
+static MESSAGE_LOCATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)^(.+?):(\d+): (.*)$").unwrap());
+
+/// `chunk:line: in ...`, a traceback frame inside a script
+static TRACEBACK_LOCATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*(.+?):(\d+): in ").unwrap());
+
+/// Names Lua quotes in its messages, e.g. `(global 'foo')` or `near 'then'`
+static QUOTED_NAME: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"'([^']+)'").unwrap());
+
+/// A Lua error pointing at the gatekeeper source it came from
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub message: String,
+    /// Where the error happened, when Lua reported a line
+    pub location: Option<Location>,
+    /// Gatekeepers from the one evaluated first down to the one that failed
+    pub chain: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Location {
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+    /// Width of the caret underline
+    pub width: usize,
+    /// Numbered source lines around `line`
+    pub context: Vec<(usize, String)>,
+}
+
+impl Diagnostic {
+    /// Describe an error from running `source`, a gatekeeper called `name` loaded from `path`.
+    /// Errors from required gatekeepers keep their own location, with `name` added to the chain.
+    pub fn from_lua_error(error: &LuaError, name: Option<&str>, path: &str, source: &str) -> Self {
+        if let Some(nested) = Self::find(error) {
+            let mut diagnostic = nested.clone();
+            if let Some(name) = name {
+                diagnostic.chain.insert(0, name.to_string());
+            }
+            return diagnostic;
+        }
+
+        let (message, traceback) = Self::message_and_traceback(error);
+        let (message, traceback) = match message.split_once("\nstack traceback:") {
+            Some((message, rest)) => (message.to_string(), traceback.or(Some(rest.to_string()))),
+            None => (message, traceback),
+        };
+
+        // Errors raised by the script carry their location; errors from Rust
+        // functions only show up in the traceback
+        let (message, position) = match MESSAGE_LOCATION.captures(&message) {
+            Some(captures) => (
+                captures[3].to_string(),
+                Some((captures[1].to_string(), captures[2].parse().unwrap_or(0))),
+            ),
+            None => {
+                let position = traceback.as_deref().and_then(|traceback| {
+                    traceback.lines().find_map(|frame| {
+                        let captures = TRACEBACK_LOCATION.captures(frame)?;
+                        Some((captures[1].to_string(), captures[2].parse().ok()?))
+                    })
+                });
+                (message, position)
+            }
+        };
+
+        let location = position.map(|(chunk, line)| {
+            // Lua shortens long chunk names to "...tail"
+            let is_own = chunk == path
+                || chunk
+                    .strip_prefix("...")
+                    .is_some_and(|tail| path.ends_with(tail));
+            let (path, text) = if is_own {
+                (path.to_string(), Some(source.to_string()))
+            } else {
+                let text = std::fs::read_to_string(&chunk).ok();
+                (chunk, text)
+            };
+            // Names quoted in the traceback, like `function 'any'`, mark what failed
+            let names = format!("{}\n{}", message, traceback.as_deref().unwrap_or(""));
+            Location::new(path, line, &names, text.as_deref())
+        });
+
+        Diagnostic {
+            message,
+            location,
+            chain: name.map(|name| vec![name.to_string()]).unwrap_or_default(),
+        }
+    }
+
+    /// The diagnostic of a required gatekeeper that failed while running this one
+    pub fn find(error: &LuaError) -> Option<&Diagnostic> {
+        match error {
+            LuaError::CallbackError { cause, .. } => Self::find(cause),
+            LuaError::ExternalError(error) => error.downcast_ref::<Diagnostic>(),
+            _ => None,
+        }
+    }
+
+    /// The innermost error message, and the traceback of the Rust function that raised it
+    fn message_and_traceback(error: &LuaError) -> (String, Option<String>) {
+        match error {
+            LuaError::CallbackError { traceback, cause } => {
+                let (message, inner) = Self::message_and_traceback(cause);
+                (message, inner.or_else(|| Some(traceback.clone())))
+            }
+            LuaError::SyntaxError { message, .. } => (message.clone(), None),
+            LuaError::RuntimeError(message) => (message.clone(), None),
+            other => (other.to_string(), None),
+        }
+    }
+}
+
+impl Location {
+    fn new(path: String, line: usize, names: &str, source: Option<&str>) -> Self {
+        let lines: Vec<&str> = source.map(|source| source.lines().collect()).unwrap_or_default();
+        let text = line.checked_sub(1).and_then(|index| lines.get(index)).copied();
+
+        // Point at a quoted name if it's on the line, else at the start of the code
+        let (column, width) = text
+            .and_then(|text| {
+                QUOTED_NAME.captures_iter(names).find_map(|captures| {
+                    let name = captures.get(1)?.as_str();
+                    let start = text.find(name)?;
+                    Some((text[..start].chars().count() + 1, name.chars().count()))
+                })
+            })
+            .or_else(|| {
+                let text = text?;
+                let start = text.len() - text.trim_start().len();
+                Some((text[..start].chars().count() + 1, 1))
+            })
+            .unwrap_or((1, 1));
+
+        let first = line.saturating_sub(CONTEXT_LINES).max(1);
+        let context = (first..=line + CONTEXT_LINES)
+            .filter_map(|number| Some((number, lines.get(number - 1)?.to_string())))
+            .collect();
+
+        Location {
+            path,
+            line,
+            column,
+            width,
+            context,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Lua execution failed: {}", self.message)?;
+
+        if let Some(location) = &self.location {
+            write!(f, "\n  --> {}:{}:{}", location.path, location.line, location.column)?;
+
+            let gutter = location
+                .context
+                .last()
+                .map_or(1, |(number, _)| number.to_string().len());
+            if !location.context.is_empty() {
+                write!(f, "\n{:gutter$} |", "")?;
+            }
+            for (number, text) in &location.context {
+                write!(f, "\n{:>gutter$} | {}", number, text)?;
+                if *number == location.line {
+                    // Keep tabs so the caret lines up with the source
+                    let indent: String = text
+                        .chars()
+                        .take(location.column - 1)
+                        .map(|c| if c == '\t' { '\t' } else { ' ' })
+                        .collect();
+                    write!(f, "\n{:gutter$} | {}{}", "", indent, "^".repeat(location.width))?;
+                }
+            }
+        }
+
+        if self.chain.len() > 1 {
+            write!(f, "\n  = require chain: {}", self.chain.join(" → "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "-- ttl: 60\nlocal on_vpn = any({})\n\nreturn on_vpn and foo()\n";
+
+    #[test]
+    fn test_runtime_error_location() {
+        let error = LuaError::RuntimeError(
+            "/gk/work/vpn.lua:4: attempt to call a nil value (global 'foo')\nstack traceback:\n\t[C]: in ?"
+                .to_string(),
+        );
+        let diagnostic =
+            Diagnostic::from_lua_error(&error, Some("work/vpn"), "/gk/work/vpn.lua", SOURCE);
+
+        assert_eq!(diagnostic.message, "attempt to call a nil value (global 'foo')");
+        let location = diagnostic.location.as_ref().unwrap();
+        assert_eq!((location.line, location.column, location.width), (4, 19, 3));
+        assert_eq!(location.context.first().map(|(number, _)| *number), Some(2));
+
+        let rendered = diagnostic.to_string();
+        assert!(rendered.contains("  --> /gk/work/vpn.lua:4:19\n"));
+        assert!(rendered.contains("4 | return on_vpn and foo()\n  |                   ^^^"));
+        assert!(!rendered.contains("require chain"));
+    }
+
+    #[test]
+    fn test_callback_error_uses_traceback() {
+        let error = LuaError::CallbackError {
+            traceback: "stack traceback:\n\t[C]: in function 'any'\n\t...ng/path/work/vpn.lua:2: in main chunk"
+                .to_string(),
+            cause: std::sync::Arc::new(LuaError::RuntimeError(
+                "any(): check #2 is string".to_string(),
+            )),
+        };
+        let diagnostic =
+            Diagnostic::from_lua_error(&error, None, "/a/very/long/path/work/vpn.lua", SOURCE);
+
+        assert_eq!(diagnostic.message, "any(): check #2 is string");
+        let location = diagnostic.location.unwrap();
+        assert_eq!(location.path, "/a/very/long/path/work/vpn.lua");
+        // No quoted name in the message, so the caret goes under the function that failed
+        assert_eq!((location.line, location.column, location.width), (2, 16, 3));
+    }
+
+    #[test]
+    fn test_nested_diagnostic_extends_chain() {
+        let inner = Diagnostic {
+            message: "boom".to_string(),
+            location: None,
+            chain: vec!["work/vpn".to_string()],
+        };
+        let error = LuaError::CallbackError {
+            traceback: String::new(),
+            cause: std::sync::Arc::new(LuaError::external(inner)),
+        };
+        let diagnostic = Diagnostic::from_lua_error(&error, Some("work"), "/gk/work.lua", "");
+        let diagnostic = Diagnostic::from_lua_error(
+            &LuaError::external(diagnostic),
+            Some("root"),
+            "/gk/root.lua",
+            "",
+        );
+
+        assert_eq!(diagnostic.message, "boom");
+        assert!(diagnostic.to_string().ends_with("\n  = require chain: root → work → work/vpn"));
+    }
+}
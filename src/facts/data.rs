@@ -0,0 +1,244 @@
+//! Querying values inside JSON, TOML, YAML and INI files.
+//!
+//! Every format is parsed into a `serde_json::Value` so a single key-path
+//! syntax ("a.b[0].c") works across all of them.
+
+use std::path::Path;
+
+use anyhow::Context;
+use anyhow::Result;
+use serde_json::Map;
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Json,
+    Toml,
+    Yaml,
+    Ini,
+}
+
+impl Format {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Json => "JSON",
+            Format::Toml => "TOML",
+            Format::Yaml => "YAML",
+            Format::Ini => "INI",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Parse a key path like `a.b[0].c` or `servers["db.primary"].host`
+fn parse_key_path(key_path: &str) -> Result<Vec<Segment>> {
+    let invalid = |reason: &str| anyhow::anyhow!("Invalid key path '{}': {}", key_path, reason);
+    let mut segments = Vec::new();
+    let mut chars = key_path.chars().peekable();
+    let mut key = String::new();
+    // A '.' directly after "[...]" separates segments without a key before it
+    let mut after_bracket = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => {
+                if !key.is_empty() {
+                    segments.push(Segment::Key(std::mem::take(&mut key)));
+                } else if !after_bracket {
+                    return Err(invalid("empty key"));
+                }
+                after_bracket = false;
+            }
+            '[' => {
+                if !key.is_empty() {
+                    segments.push(Segment::Key(std::mem::take(&mut key)));
+                }
+                let mut inner = String::new();
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some(c) => inner.push(c),
+                        None => return Err(invalid("unclosed '['")),
+                    }
+                }
+                let quoted = inner
+                    .strip_prefix('"')
+                    .and_then(|s| s.strip_suffix('"'))
+                    .or_else(|| inner.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')));
+                match quoted {
+                    Some(name) => segments.push(Segment::Key(name.to_string())),
+                    None => segments.push(Segment::Index(
+                        inner
+                            .trim()
+                            .parse()
+                            .map_err(|_| invalid("index must be a number or a quoted key"))?,
+                    )),
+                }
+                after_bracket = true;
+            }
+            _ => {
+                key.push(c);
+                after_bracket = false;
+            }
+        }
+    }
+    if !key.is_empty() {
+        segments.push(Segment::Key(key));
+    } else if key_path.ends_with('.') {
+        return Err(invalid("empty key"));
+    }
+    Ok(segments)
+}
+
+/// Look up a key path in a parsed document; an empty path returns the whole document
+pub fn query<'a>(document: &'a Value, key_path: &str) -> Result<Option<&'a Value>> {
+    let mut current = document;
+    for segment in parse_key_path(key_path)? {
+        let next = match (&segment, current) {
+            (Segment::Key(key), Value::Object(map)) => map.get(key),
+            (Segment::Index(index), Value::Array(items)) => items.get(*index),
+            _ => None,
+        };
+        match next {
+            Some(value) => current = value,
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(current))
+}
+
+/// Parse a file, returning `None` if it doesn't exist
+pub fn load(path: &Path, format: Format) -> Result<Option<Value>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {} file {}", format.name(), path.display()))?;
+    let document = match format {
+        Format::Json => serde_json::from_str(&content).map_err(anyhow::Error::from),
+        Format::Toml => toml::from_str(&content).map_err(anyhow::Error::from),
+        Format::Yaml => serde_yaml::from_str(&content).map_err(anyhow::Error::from),
+        Format::Ini => Ok(parse_ini(&content)),
+    }
+    .with_context(|| format!("Failed to parse {} file {}", format.name(), path.display()))?;
+    Ok(Some(document))
+}
+
+/// INI keys before any section are top-level; `[section]` keys nest under the section
+fn parse_ini(content: &str) -> Value {
+    let mut root = Map::new();
+    let mut section: Option<String> = None;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            let name = name.trim().to_string();
+            root.entry(name.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            section = Some(name);
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=').or_else(|| line.split_once(':')) else {
+            continue;
+        };
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(value);
+        let target = match &section {
+            Some(name) => root
+                .get_mut(name)
+                .and_then(Value::as_object_mut)
+                .expect("section inserted above"),
+            None => &mut root,
+        };
+        target.insert(key.trim().to_string(), Value::String(value.to_string()));
+    }
+    Value::Object(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::facts::test_utils::create_fixture_root;
+
+    #[test]
+    fn test_parse_key_path() -> Result<()> {
+        assert_eq!(
+            parse_key_path("a.b[0].c")?,
+            vec![
+                Segment::Key("a".into()),
+                Segment::Key("b".into()),
+                Segment::Index(0),
+                Segment::Key("c".into()),
+            ]
+        );
+        assert_eq!(
+            parse_key_path(r#"servers["db.primary"].host"#)?,
+            vec![
+                Segment::Key("servers".into()),
+                Segment::Key("db.primary".into()),
+                Segment::Key("host".into()),
+            ]
+        );
+        assert_eq!(parse_key_path("")?, vec![]);
+        assert!(parse_key_path("a..b").is_err());
+        assert!(parse_key_path("a[x]").is_err());
+        assert!(parse_key_path("a[0").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_json() -> Result<()> {
+        let document: Value = serde_json::from_str(
+            r#"{"cpe": {"tags": [{"name": "corp"}, {"name": "laptop"}], "managed": true}}"#,
+        )?;
+        assert_eq!(query(&document, "cpe.tags[1].name")?, Some(&Value::from("laptop")));
+        assert_eq!(query(&document, "cpe.managed")?, Some(&Value::from(true)));
+        assert_eq!(query(&document, "cpe.tags[5].name")?, None);
+        assert_eq!(query(&document, "cpe.managed.nested")?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_formats() -> Result<()> {
+        let root = create_fixture_root(&[
+            ("config.toml", "[server]\nport = 8080\nhosts = [\"a\", \"b\"]\n"),
+            ("config.yaml", "server:\n  port: 8080\n  hosts:\n    - a\n    - b\n"),
+            ("config.ini", "name = top\n; comment\n[server]\nport = 8080\nhost = \"a\"\n"),
+        ])?;
+
+        let toml = load(&root.path().join("config.toml"), Format::Toml)?.unwrap();
+        assert_eq!(query(&toml, "server.port")?, Some(&Value::from(8080)));
+        assert_eq!(query(&toml, "server.hosts[1]")?, Some(&Value::from("b")));
+
+        let yaml = load(&root.path().join("config.yaml"), Format::Yaml)?.unwrap();
+        assert_eq!(query(&yaml, "server.port")?, Some(&Value::from(8080)));
+        assert_eq!(query(&yaml, "server.hosts[0]")?, Some(&Value::from("a")));
+
+        let ini = load(&root.path().join("config.ini"), Format::Ini)?.unwrap();
+        assert_eq!(query(&ini, "name")?, Some(&Value::from("top")));
+        assert_eq!(query(&ini, "server.port")?, Some(&Value::from("8080")));
+        assert_eq!(query(&ini, "server.host")?, Some(&Value::from("a")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_missing_and_invalid() -> Result<()> {
+        let root = create_fixture_root(&[("broken.json", "{\"a\": ")])?;
+        assert!(load(&root.path().join("missing.json"), Format::Json)?.is_none());
+
+        let err = load(&root.path().join("broken.json"), Format::Json).unwrap_err();
+        assert!(format!("{:#}", err).contains("Failed to parse JSON file"));
+        assert!(format!("{:#}", err).contains("broken.json"));
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+//! Detectors for the environment dotgk is running in.
+//!
+//! Each detector returns a description of the signal that matched, so callers
+//! can trace why a check passed. Environment variables are looked up through
+//! `env` so tests don't depend on the calling process.
+
+use std::path::Path;
+
+use crate::facts::read_file;
+use crate::facts::resolve;
+
+/// Markers in `/proc/1/cgroup` left by common container runtimes
+const CGROUP_MARKERS: &[&str] = &["docker", "kubepods", "containerd", "libpod", "lxc"];
+
+/// DMI product names and vendors reported by common hypervisors
+const VM_MARKERS: &[&str] = &[
+    "virtualbox",
+    "vmware",
+    "kvm",
+    "qemu",
+    "virtual machine",
+    "xen",
+    "bochs",
+    "parallels",
+];
+
+/// Look up an environment variable of the current process
+pub fn process_env(name: &str) -> Option<String> {
+    std::env::var(name).ok()
+}
+
+pub fn container_signal(root: &Path) -> Option<String> {
+    for marker in ["/.dockerenv", "/run/.containerenv"] {
+        if resolve(root, marker).exists() {
+            return Some(format!("{} exists", marker));
+        }
+    }
+
+    let cgroup = read_file(root, "/proc/1/cgroup")?;
+    CGROUP_MARKERS
+        .iter()
+        .find(|marker| cgroup.contains(*marker))
+        .map(|marker| format!("/proc/1/cgroup mentions '{}'", marker))
+}
+
+pub fn wsl_signal(root: &Path, env: impl Fn(&str) -> Option<String>) -> Option<String> {
+    if env("WSL_INTEROP").is_some() {
+        return Some("WSL_INTEROP is set".to_string());
+    }
+
+    let version = read_file(root, "/proc/version")?;
+    version
+        .to_lowercase()
+        .contains("microsoft")
+        .then(|| "/proc/version mentions 'microsoft'".to_string())
+}
+
+pub fn vm_signal(root: &Path) -> Option<String> {
+    for path in ["/sys/class/dmi/id/product_name", "/sys/class/dmi/id/sys_vendor"] {
+        let Some(value) = read_file(root, path) else {
+            continue;
+        };
+        let value = value.trim().to_lowercase();
+        if let Some(marker) = VM_MARKERS.iter().find(|marker| value.contains(*marker)) {
+            return Some(format!("{} mentions '{}'", path, marker));
+        }
+    }
+    None
+}
+
+pub fn ssh_signal(env: impl Fn(&str) -> Option<String>) -> Option<String> {
+    ["SSH_CONNECTION", "SSH_CLIENT", "SSH_TTY"]
+        .iter()
+        .find(|name| env(name).is_some_and(|value| !value.is_empty()))
+        .map(|name| format!("{} is set", name))
+}
+
+pub fn tmux_signal(env: impl Fn(&str) -> Option<String>) -> Option<String> {
+    env("TMUX")
+        .filter(|value| !value.is_empty())
+        .map(|_| "TMUX is set".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use anyhow::Result;
+
+    use super::*;
+    use crate::facts::test_utils::create_fixture_root;
+
+    fn env_from(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_container_signal() -> Result<()> {
+        let docker = create_fixture_root(&[(".dockerenv", "")])?;
+        assert_eq!(
+            container_signal(docker.path()).as_deref(),
+            Some("/.dockerenv exists")
+        );
+
+        let podman = create_fixture_root(&[("run/.containerenv", "")])?;
+        assert!(container_signal(podman.path()).is_some());
+
+        let k8s = create_fixture_root(&[("proc/1/cgroup", "0::/kubepods/besteffort/pod1234\n")])?;
+        assert_eq!(
+            container_signal(k8s.path()).as_deref(),
+            Some("/proc/1/cgroup mentions 'kubepods'")
+        );
+
+        let host = create_fixture_root(&[("proc/1/cgroup", "0::/init.scope\n")])?;
+        assert!(container_signal(host.path()).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_wsl_signal() -> Result<()> {
+        let empty = create_fixture_root(&[])?;
+        assert!(wsl_signal(empty.path(), env_from(&[("WSL_INTEROP", "/run/WSL/1_interop")])).is_some());
+        assert!(wsl_signal(empty.path(), env_from(&[])).is_none());
+
+        let wsl = create_fixture_root(&[(
+            "proc/version",
+            "Linux version 5.15.133.1-microsoft-standard-WSL2 (gcc)\n",
+        )])?;
+        assert!(wsl_signal(wsl.path(), env_from(&[])).is_some());
+
+        let native = create_fixture_root(&[("proc/version", "Linux version 6.5.0-35-generic\n")])?;
+        assert!(wsl_signal(native.path(), env_from(&[])).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_vm_signal() -> Result<()> {
+        let kvm = create_fixture_root(&[("sys/class/dmi/id/product_name", "Standard PC (Q35 + ICH9, 2009)\n"), ("sys/class/dmi/id/sys_vendor", "QEMU\n")])?;
+        assert_eq!(
+            vm_signal(kvm.path()).as_deref(),
+            Some("/sys/class/dmi/id/sys_vendor mentions 'qemu'")
+        );
+
+        let hyperv = create_fixture_root(&[("sys/class/dmi/id/product_name", "Virtual Machine\n")])?;
+        assert!(vm_signal(hyperv.path()).is_some());
+
+        let metal = create_fixture_root(&[("sys/class/dmi/id/product_name", "ThinkPad X1 Carbon\n")])?;
+        assert!(vm_signal(metal.path()).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_ssh_and_tmux_signals() {
+        assert_eq!(
+            ssh_signal(env_from(&[("SSH_CONNECTION", "10.0.0.2 5000 10.0.0.1 22")])).as_deref(),
+            Some("SSH_CONNECTION is set")
+        );
+        assert!(ssh_signal(env_from(&[("SSH_TTY", "")])).is_none());
+        assert!(tmux_signal(env_from(&[("TMUX", "/tmp/tmux-1000/default,123,0")])).is_some());
+        assert!(tmux_signal(env_from(&[])).is_none());
+    }
+}
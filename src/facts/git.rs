@@ -0,0 +1,144 @@
+//! Git repository state read straight from `.git`, without running `git`.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Repository {
+    /// Per-worktree directory holding `HEAD`
+    pub git_dir: PathBuf,
+    /// Directory shared by all worktrees, holding `config`
+    pub common_dir: PathBuf,
+}
+
+/// Find the repository containing `start`, walking up through its parents.
+///
+/// A `.git` file (as in worktrees and submodules) points at the real git
+/// directory with a `gitdir:` line, and a worktree's `commondir` file points
+/// at the main repository's directory.
+pub fn discover(start: &Path) -> Option<Repository> {
+    for dir in start.ancestors() {
+        let dot_git = dir.join(".git");
+        let git_dir = if dot_git.is_dir() {
+            dot_git
+        } else if dot_git.is_file() {
+            let content = std::fs::read_to_string(&dot_git).ok()?;
+            let target = content.lines().find_map(|line| line.strip_prefix("gitdir:"))?;
+            dir.join(target.trim())
+        } else {
+            continue;
+        };
+
+        if !git_dir.join("HEAD").is_file() {
+            return None;
+        }
+        let common_dir = match std::fs::read_to_string(git_dir.join("commondir")) {
+            Ok(common) => git_dir.join(common.trim()),
+            Err(_) => git_dir.clone(),
+        };
+        return Some(Repository { git_dir, common_dir });
+    }
+    None
+}
+
+impl Repository {
+    /// Checked-out branch name, or `None` for a detached `HEAD`
+    pub fn branch(&self) -> Option<String> {
+        let head = std::fs::read_to_string(self.git_dir.join("HEAD")).ok()?;
+        head.trim()
+            .strip_prefix("ref:")
+            .map(str::trim)
+            .map(|reference| reference.strip_prefix("refs/heads/").unwrap_or(reference).to_string())
+    }
+
+    /// URLs of every configured remote
+    pub fn remote_urls(&self) -> Vec<String> {
+        std::fs::read_to_string(self.common_dir.join("config"))
+            .map(|config| parse_remote_urls(&config))
+            .unwrap_or_default()
+    }
+}
+
+fn parse_remote_urls(config: &str) -> Vec<String> {
+    let mut urls = Vec::new();
+    let mut in_remote = false;
+    for line in config.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_remote = line
+                .trim_start_matches('[')
+                .split_whitespace()
+                .next()
+                .is_some_and(|section| section.eq_ignore_ascii_case("remote"));
+            continue;
+        }
+        if !in_remote {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=')
+            && key.trim().eq_ignore_ascii_case("url")
+        {
+            urls.push(value.trim().trim_matches('"').to_string());
+        }
+    }
+    urls
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::facts::test_utils::create_fixture_root;
+
+    const CONFIG: &str = r#"[core]
+	bare = false
+[remote "origin"]
+	url = git@github.com:corp/dotfiles.git
+	fetch = +refs/heads/*:refs/remotes/origin/*
+[branch "main"]
+	remote = origin
+[remote "upstream"]
+	url = "https://github.com/someone/dotfiles"
+"#;
+
+    #[test]
+    fn test_discover_from_subdirectory() -> Result<()> {
+        let root = create_fixture_root(&[
+            ("repo/.git/HEAD", "ref: refs/heads/feature/x\n"),
+            ("repo/.git/config", CONFIG),
+            ("repo/src/lib/mod.rs", ""),
+        ])?;
+        let repo = discover(&root.path().join("repo/src/lib")).unwrap();
+        assert_eq!(repo.git_dir, root.path().join("repo/.git"));
+        assert_eq!(repo.branch().as_deref(), Some("feature/x"));
+        assert_eq!(
+            repo.remote_urls(),
+            vec!["git@github.com:corp/dotfiles.git", "https://github.com/someone/dotfiles"]
+        );
+        assert_eq!(discover(&root.path().join("elsewhere")), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_discover_worktree() -> Result<()> {
+        let root = create_fixture_root(&[
+            ("main/.git/HEAD", "ref: refs/heads/main\n"),
+            ("main/.git/config", CONFIG),
+            ("main/.git/worktrees/wt/HEAD", "0123456789abcdef0123456789abcdef01234567\n"),
+            ("main/.git/worktrees/wt/commondir", "../..\n"),
+            ("wt/.git", "gitdir: ../main/.git/worktrees/wt\n"),
+        ])?;
+        let repo = discover(&root.path().join("wt")).unwrap();
+        assert_eq!(repo.branch(), None);
+        assert_eq!(repo.remote_urls().len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_broken_gitdir_file() -> Result<()> {
+        let root = create_fixture_root(&[("repo/.git", "gitdir: ../missing\n")])?;
+        assert_eq!(discover(&root.path().join("repo")), None);
+        Ok(())
+    }
+}
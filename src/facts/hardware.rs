@@ -0,0 +1,111 @@
+use std::path::Path;
+
+use crate::facts::read_file;
+use crate::facts::resolve;
+
+/// Canonical architecture name for common aliases ("arm64" -> "aarch64")
+pub fn normalize_arch(name: &str) -> String {
+    match name.to_lowercase().as_str() {
+        "arm64" => "aarch64".to_string(),
+        "amd64" | "x64" => "x86_64".to_string(),
+        "i386" | "i686" => "x86".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Architecture the binary was built for, e.g. "x86_64" or "aarch64"
+pub fn arch() -> &'static str {
+    std::env::consts::ARCH
+}
+
+/// Number of logical CPUs from `/proc/cpuinfo`, falling back to the OS-reported parallelism
+pub fn cpu_count(root: &Path) -> usize {
+    read_file(root, "/proc/cpuinfo")
+        .map(|cpuinfo| {
+            cpuinfo
+                .lines()
+                .filter(|line| line.split(':').next().map(str::trim) == Some("processor"))
+                .count()
+        })
+        .filter(|&count| count > 0)
+        .or_else(|| std::thread::available_parallelism().ok().map(|n| n.get()))
+        .unwrap_or(1)
+}
+
+/// Total memory in MiB from the `MemTotal` line of `/proc/meminfo`
+pub fn memory_total_mb(root: &Path) -> Option<u64> {
+    let meminfo = read_file(root, "/proc/meminfo")?;
+    meminfo.lines().find_map(|line| {
+        let rest = line.strip_prefix("MemTotal:")?;
+        let kb = rest.split_whitespace().next()?.parse::<u64>().ok()?;
+        Some(kb / 1024)
+    })
+}
+
+/// Whether any entry under `/sys/class/power_supply` is a battery
+pub fn has_battery(root: &Path) -> bool {
+    let Ok(entries) = std::fs::read_dir(resolve(root, "/sys/class/power_supply")) else {
+        return false;
+    };
+    entries.flatten().any(|entry| {
+        std::fs::read_to_string(entry.path().join("type"))
+            .is_ok_and(|kind| kind.trim() == "Battery")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::facts::test_utils::create_fixture_root;
+
+    #[test]
+    fn test_normalize_arch() {
+        assert_eq!(normalize_arch("arm64"), "aarch64");
+        assert_eq!(normalize_arch("AMD64"), "x86_64");
+        assert_eq!(normalize_arch("riscv64"), "riscv64");
+    }
+
+    #[test]
+    fn test_cpu_count_from_cpuinfo() -> Result<()> {
+        let cpuinfo = "processor\t: 0\nmodel name\t: Fake CPU\n\nprocessor\t: 1\nmodel name\t: Fake CPU\n\nprocessor\t: 2\n";
+        let root = create_fixture_root(&[("proc/cpuinfo", cpuinfo)])?;
+        assert_eq!(cpu_count(root.path()), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cpu_count_fallback() -> Result<()> {
+        let root = create_fixture_root(&[])?;
+        assert!(cpu_count(root.path()) >= 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_memory_total_mb() -> Result<()> {
+        let meminfo = "MemTotal:       16318464 kB\nMemFree:         1234567 kB\n";
+        let root = create_fixture_root(&[("proc/meminfo", meminfo)])?;
+        assert_eq!(memory_total_mb(root.path()), Some(15936));
+
+        let empty = create_fixture_root(&[])?;
+        assert_eq!(memory_total_mb(empty.path()), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_has_battery() -> Result<()> {
+        let laptop = create_fixture_root(&[
+            ("sys/class/power_supply/AC/type", "Mains\n"),
+            ("sys/class/power_supply/BAT0/type", "Battery\n"),
+        ])?;
+        assert!(has_battery(laptop.path()));
+
+        let server = create_fixture_root(&[("sys/class/power_supply/AC/type", "Mains\n")])?;
+        assert!(!has_battery(server.path()));
+
+        let empty = create_fixture_root(&[])?;
+        assert!(!has_battery(empty.path()));
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+use std::path::Path;
+
+use anyhow::Context;
+use anyhow::Result;
+
+use crate::facts::read_file;
+
+/// Hostname as reported by the operating system
+pub fn hostname() -> Result<String> {
+    let current = hostname::get().context("Failed to get hostname")?;
+    current
+        .into_string()
+        .map_err(|_| anyhow::anyhow!("Invalid hostname encoding"))
+}
+
+/// Hostname without any domain part: "devvm1.corp" -> "devvm1"
+pub fn short_hostname(hostname: &str) -> &str {
+    hostname.split('.').next().unwrap_or(hostname)
+}
+
+/// Resolve the fully qualified domain name using local sources only.
+///
+/// Tries, in order: the hostname itself if it already contains a dot, the
+/// canonical name of a matching `/etc/hosts` entry, and the `domain` or first
+/// `search` entry of `/etc/resolv.conf`. Falls back to the plain hostname.
+pub fn fqdn(root: &Path, hostname: &str) -> String {
+    if hostname.contains('.') {
+        return hostname.to_string();
+    }
+
+    if let Some(hosts) = read_file(root, "/etc/hosts")
+        && let Some(name) = fqdn_from_hosts(&hosts, hostname)
+    {
+        return name;
+    }
+
+    if let Some(resolv) = read_file(root, "/etc/resolv.conf")
+        && let Some(domain) = domain_from_resolv_conf(&resolv)
+    {
+        return format!("{}.{}", hostname, domain);
+    }
+
+    hostname.to_string()
+}
+
+fn fqdn_from_hosts(hosts: &str, hostname: &str) -> Option<String> {
+    for line in hosts.lines() {
+        let line = line.split('#').next().unwrap_or("");
+        // First field is the address, the rest are names
+        let names: Vec<&str> = line.split_whitespace().skip(1).collect();
+        if names.iter().any(|name| short_hostname(name) == hostname)
+            && let Some(name) = names.iter().find(|name| name.contains('.'))
+        {
+            return Some(name.to_string());
+        }
+    }
+    None
+}
+
+fn domain_from_resolv_conf(resolv: &str) -> Option<String> {
+    let mut search = None;
+    for line in resolv.lines() {
+        let mut fields = line.split_whitespace();
+        match fields.next() {
+            Some("domain") => return fields.next().map(|s| s.to_string()),
+            Some("search") if search.is_none() => search = fields.next().map(|s| s.to_string()),
+            _ => {}
+        }
+    }
+    search
+}
+
+/// Check a glob pattern (`*`, `?`, `[...]`) against a list of candidate names
+pub fn matches_glob(pattern: &str, candidates: &[String]) -> Result<bool> {
+    let pattern = glob::Pattern::new(pattern)
+        .with_context(|| format!("Invalid hostname pattern '{}'", pattern))?;
+    Ok(candidates.iter().any(|candidate| pattern.matches(candidate)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::facts::test_utils::create_fixture_root;
+
+    #[test]
+    fn test_short_hostname() {
+        assert_eq!(short_hostname("devvm1.corp.example.com"), "devvm1");
+        assert_eq!(short_hostname("laptop"), "laptop");
+    }
+
+    #[test]
+    fn test_fqdn_already_qualified() -> Result<()> {
+        let root = create_fixture_root(&[])?;
+        assert_eq!(fqdn(root.path(), "devvm1.corp"), "devvm1.corp");
+        Ok(())
+    }
+
+    #[test]
+    fn test_fqdn_from_hosts() -> Result<()> {
+        let root = create_fixture_root(&[(
+            "etc/hosts",
+            "127.0.0.1 localhost\n# comment devvm1.wrong\n10.0.0.5 devvm1.corp.example.com devvm1\n",
+        )])?;
+        assert_eq!(fqdn(root.path(), "devvm1"), "devvm1.corp.example.com");
+        Ok(())
+    }
+
+    #[test]
+    fn test_fqdn_from_resolv_conf() -> Result<()> {
+        let root = create_fixture_root(&[
+            ("etc/hosts", "127.0.0.1 localhost\n"),
+            ("etc/resolv.conf", "nameserver 10.0.0.1\nsearch corp.example.com example.com\n"),
+        ])?;
+        assert_eq!(fqdn(root.path(), "devvm1"), "devvm1.corp.example.com");
+        Ok(())
+    }
+
+    #[test]
+    fn test_fqdn_fallback() -> Result<()> {
+        let root = create_fixture_root(&[])?;
+        assert_eq!(fqdn(root.path(), "laptop"), "laptop");
+        Ok(())
+    }
+
+    #[test]
+    fn test_matches_glob() -> Result<()> {
+        let candidates = vec!["devvm42".to_string(), "devvm42.corp".to_string()];
+        assert!(matches_glob("devvm*.corp", &candidates)?);
+        assert!(matches_glob("devvm42", &candidates)?);
+        assert!(!matches_glob("laptop*", &candidates)?);
+        assert!(matches_glob("[", &candidates).is_err());
+        Ok(())
+    }
+}
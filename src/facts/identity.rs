@@ -0,0 +1,126 @@
+//! Stable machine identity and percentage rollouts keyed on it.
+
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::facts::read_file;
+
+/// Stable identifier for this machine.
+///
+/// `DOTGK_MACHINE_ID` overrides detection; otherwise `/etc/machine-id`, then
+/// `/var/lib/dbus/machine-id`, then the hostname.
+pub fn machine_id(root: &Path) -> Option<String> {
+    if let Ok(id) = std::env::var("DOTGK_MACHINE_ID")
+        && !id.trim().is_empty()
+    {
+        return Some(id.trim().to_string());
+    }
+
+    ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+        .iter()
+        .filter_map(|path| read_file(root, path))
+        .map(|id| id.trim().to_string())
+        .find(|id| !id.is_empty())
+        .or_else(|| crate::facts::host::hostname().ok())
+}
+
+/// 64-bit FNV-1a with a splitmix64 finalizer, stable across platforms and releases
+fn stable_hash(input: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in input.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash ^= hash >> 30;
+    hash = hash.wrapping_mul(0xbf58476d1ce4e5b9);
+    hash ^= hash >> 27;
+    hash = hash.wrapping_mul(0x94d049bb133111eb);
+    hash ^ (hash >> 31)
+}
+
+/// Bucket in `[0, 10000)` for a flag on a given machine key
+pub fn rollout_bucket(flag: &str, key: &str, salt: &str) -> u64 {
+    stable_hash(&format!("{}:{}:{}", salt, flag, key)) % 10_000
+}
+
+/// Whether `flag` is enabled for `key` at `percent` (0-100, fractions allowed).
+///
+/// Buckets are stable, so raising the percentage only ever adds machines.
+pub fn rollout(flag: &str, percent: f64, key: &str, salt: &str) -> Result<bool> {
+    if !(0.0..=100.0).contains(&percent) {
+        anyhow::bail!(
+            "rollout percentage for '{}' must be between 0 and 100, got {}",
+            flag,
+            percent
+        );
+    }
+    let threshold = (percent * 100.0).round() as u64;
+    Ok(rollout_bucket(flag, key, salt) < threshold)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::facts::test_utils::create_fixture_root;
+
+    #[test]
+    fn test_machine_id_from_etc() -> Result<()> {
+        let root = create_fixture_root(&[("etc/machine-id", "4f1c9a0e8b2d4c6e9f0a1b2c3d4e5f60\n")])?;
+        if std::env::var("DOTGK_MACHINE_ID").is_err() {
+            assert_eq!(
+                machine_id(root.path()).as_deref(),
+                Some("4f1c9a0e8b2d4c6e9f0a1b2c3d4e5f60")
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_machine_id_dbus_fallback() -> Result<()> {
+        let root = create_fixture_root(&[
+            ("etc/machine-id", "\n"),
+            ("var/lib/dbus/machine-id", "0123456789abcdef\n"),
+        ])?;
+        if std::env::var("DOTGK_MACHINE_ID").is_err() {
+            assert_eq!(machine_id(root.path()).as_deref(), Some("0123456789abcdef"));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_rollout_is_deterministic() -> Result<()> {
+        let first = rollout("new_prompt", 25.0, "machine-a", "")?;
+        for _ in 0..10 {
+            assert_eq!(rollout("new_prompt", 25.0, "machine-a", "")?, first);
+        }
+        assert!(rollout("new_prompt", 100.0, "machine-a", "")?);
+        assert!(!rollout("new_prompt", 0.0, "machine-a", "")?);
+        assert!(rollout("new_prompt", 101.0, "machine-a", "").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rollout_distribution() -> Result<()> {
+        let enabled = (0..10_000)
+            .filter(|i| rollout("new_prompt", 25.0, &format!("machine-{}", i), "").unwrap())
+            .count();
+        // Roughly a quarter of the fleet, well within sampling noise
+        assert!((2_250..=2_750).contains(&enabled), "enabled {}", enabled);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rollout_is_monotonic_and_salted() -> Result<()> {
+        for i in 0..1_000 {
+            let key = format!("machine-{}", i);
+            if rollout("flag", 10.0, &key, "")? {
+                assert!(rollout("flag", 50.0, &key, "")?);
+            }
+        }
+        let unsalted: Vec<u64> = (0..20).map(|i| rollout_bucket("flag", &i.to_string(), "")).collect();
+        let salted: Vec<u64> = (0..20).map(|i| rollout_bucket("flag", &i.to_string(), "v2")).collect();
+        assert_ne!(unsalted, salted);
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+//! Readers for local machine state used by the Lua DSL predicates.
+//!
+//! Every reader takes a `root` directory that system paths are resolved
+//! against, so tests can point it at a fixture tree instead of `/`.
+
+pub mod data;
+pub mod environment;
+pub mod git;
+pub mod hardware;
+pub mod host;
+pub mod identity;
+pub mod mounts;
+pub mod network;
+pub mod os_release;
+pub mod paths;
+pub mod probe;
+pub mod process;
+pub mod time;
+pub mod user;
+pub mod version;
+
+#[cfg(test)]
+pub mod test_utils;
+
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Root directory for system files, `/` unless overridden by `DOTGK_ROOT`
+pub fn root() -> PathBuf {
+    std::env::var_os("DOTGK_ROOT")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/"))
+}
+
+/// Resolve an absolute system path (e.g. `/etc/hosts`) under `root`
+pub fn resolve(root: &Path, path: &str) -> PathBuf {
+    root.join(path.trim_start_matches('/'))
+}
+
+/// Read a system file under `root`, returning `None` if it is missing or unreadable
+pub fn read_file(root: &Path, path: &str) -> Option<String> {
+    std::fs::read_to_string(resolve(root, path)).ok()
+}
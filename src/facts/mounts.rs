@@ -0,0 +1,153 @@
+//! Mounted filesystems, parsed from `/proc/self/mounts`.
+
+use std::path::Path;
+
+use crate::facts::read_file;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mount {
+    pub device: String,
+    pub mount_point: String,
+    pub fs_type: String,
+}
+
+/// All mounts in mount order; later entries shadow earlier ones at the same point
+pub fn read(root: &Path) -> Vec<Mount> {
+    read_file(root, "/proc/self/mounts")
+        .map(|content| parse_mounts(&content))
+        .unwrap_or_default()
+}
+
+fn parse_mounts(content: &str) -> Vec<Mount> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            Some(Mount {
+                device: unescape(fields.next()?),
+                mount_point: unescape(fields.next()?),
+                fs_type: fields.next()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Undo the kernel's octal escapes for whitespace and backslashes ("\040" -> " ")
+fn unescape(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\'
+            && let Some(octal) = field.get(i + 1..i + 4)
+            && let Ok(byte) = u8::from_str_radix(octal, 8)
+        {
+            out.push(byte);
+            i += 4;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Strip trailing slashes so "/mnt/c/" and "/mnt/c" compare equal
+fn normalize(path: &str) -> &str {
+    let trimmed = path.trim_end_matches('/');
+    if trimmed.is_empty() { "/" } else { trimmed }
+}
+
+/// Whether `path` is itself a mount point
+pub fn is_mounted(mounts: &[Mount], path: &str) -> bool {
+    let path = normalize(path);
+    mounts.iter().any(|mount| mount.mount_point == path)
+}
+
+/// The mount that `path` lives on: the most recent mount at the longest
+/// mount point that contains it
+pub fn containing<'a>(mounts: &'a [Mount], path: &str) -> Option<&'a Mount> {
+    let path = normalize(path);
+    let contains = |point: &str| {
+        point == "/"
+            || path == point
+            || path.strip_prefix(point).is_some_and(|rest| rest.starts_with('/'))
+    };
+    mounts
+        .iter()
+        .enumerate()
+        .filter(|(_, mount)| contains(&mount.mount_point))
+        .max_by_key(|(index, mount)| (mount.mount_point.len(), *index))
+        .map(|(_, mount)| mount)
+}
+
+/// Whether a filesystem type matches a requested one.
+///
+/// Besides an exact match, "nfs" matches versioned types like "nfs4" and
+/// "sshfs" matches FUSE subtypes like "fuse.sshfs".
+pub fn fs_type_matches(actual: &str, wanted: &str) -> bool {
+    actual == wanted
+        || actual
+            .strip_prefix(wanted)
+            .is_some_and(|rest| !rest.is_empty() && rest.bytes().all(|b| b.is_ascii_digit()))
+        || actual.strip_prefix("fuse.") == Some(wanted)
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::facts::test_utils::create_fixture_root;
+
+    const MOUNTS: &str = "\
+/dev/sda2 / ext4 rw,relatime 0 0
+proc /proc proc rw,nosuid 0 0
+C:\\134 /mnt/c 9p rw,noatime 0 0
+filer:/home /home nfs4 rw,vers=4.2 0 0
+tmpfs /home/alice/My\\040Drive tmpfs rw 0 0
+alice@host:/srv /home/alice/My\\040Drive fuse.sshfs rw 0 0
+";
+
+    #[test]
+    fn test_parse_mounts() -> Result<()> {
+        let root = create_fixture_root(&[("proc/self/mounts", MOUNTS)])?;
+        let mounts = read(root.path());
+        assert_eq!(mounts.len(), 6);
+        assert_eq!(mounts[2].device, "C:\\");
+        assert_eq!(mounts[4].mount_point, "/home/alice/My Drive");
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_mounted() {
+        let mounts = parse_mounts(MOUNTS);
+        assert!(is_mounted(&mounts, "/mnt/c"));
+        assert!(is_mounted(&mounts, "/mnt/c/"));
+        assert!(is_mounted(&mounts, "/"));
+        assert!(!is_mounted(&mounts, "/mnt"));
+        assert!(!is_mounted(&mounts, "/mnt/cd"));
+    }
+
+    #[test]
+    fn test_containing_mount() {
+        let mounts = parse_mounts(MOUNTS);
+        let fs_type = |path| containing(&mounts, path).map(|mount| mount.fs_type.as_str());
+        assert_eq!(fs_type("/home"), Some("nfs4"));
+        assert_eq!(fs_type("/home/alice/src"), Some("nfs4"));
+        assert_eq!(fs_type("/homes"), Some("ext4"));
+        // The later mount shadows the earlier one at the same point
+        assert_eq!(fs_type("/home/alice/My Drive/doc"), Some("fuse.sshfs"));
+        assert_eq!(containing(&[], "/"), None);
+    }
+
+    #[test]
+    fn test_fs_type_matches() {
+        assert!(fs_type_matches("nfs4", "nfs"));
+        assert!(fs_type_matches("nfs", "nfs"));
+        assert!(fs_type_matches("fuse.sshfs", "sshfs"));
+        assert!(fs_type_matches("fuse.sshfs", "fuse.sshfs"));
+        assert!(!fs_type_matches("nfsd", "nfs"));
+        assert!(!fs_type_matches("ext4", "ext3"));
+    }
+}
@@ -0,0 +1,344 @@
+//! Network state read from the local kernel, without any network access.
+
+use std::fmt;
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
+use std::net::Ipv6Addr;
+use std::path::Path;
+
+use anyhow::Context;
+use anyhow::Result;
+
+use crate::facts::read_file;
+
+/// `IFF_UP` from `<net/if.h>`
+const IFF_UP: u32 = 0x1;
+/// `RTF_UP` and `RTF_REJECT` from `<linux/route.h>`
+const RTF_UP: u32 = 0x1;
+const RTF_REJECT: u32 = 0x200;
+/// `TCP_LISTEN` socket state in `/proc/net/tcp`
+const TCP_LISTEN: &str = "0A";
+
+/// Whether an interface is administratively up with a usable link.
+///
+/// Tunnel interfaces such as WireGuard report an `unknown` operstate, so for
+/// those the `IFF_UP` flag decides.
+pub fn interface_up(root: &Path, name: &str) -> bool {
+    let dir = format!("/sys/class/net/{}", name);
+    match read_file(root, &format!("{}/operstate", dir)).as_deref().map(str::trim) {
+        Some("up") => true,
+        Some("unknown") => read_file(root, &format!("{}/flags", dir))
+            .and_then(|flags| u32::from_str_radix(flags.trim().trim_start_matches("0x"), 16).ok())
+            .is_some_and(|flags| flags & IFF_UP != 0),
+        _ => false,
+    }
+}
+
+/// Addresses assigned to local interfaces, from `/proc/net/fib_trie` and
+/// `/proc/net/if_inet6`, falling back to `getifaddrs` where `/proc` isn't available
+pub fn local_addresses(root: &Path) -> Vec<IpAddr> {
+    let mut addresses = Vec::new();
+    if let Some(fib_trie) = read_file(root, "/proc/net/fib_trie") {
+        addresses.extend(parse_fib_trie(&fib_trie).into_iter().map(IpAddr::V4));
+    }
+    if let Some(if_inet6) = read_file(root, "/proc/net/if_inet6") {
+        addresses.extend(parse_if_inet6(&if_inet6).into_iter().map(IpAddr::V6));
+    }
+    if addresses.is_empty() && !crate::facts::resolve(root, "/proc/net").exists() {
+        addresses = addresses_from_libc();
+    }
+    addresses.sort();
+    addresses.dedup();
+    addresses
+}
+
+/// Local addresses are the leaves followed by a `/32 host LOCAL` line
+fn parse_fib_trie(content: &str) -> Vec<Ipv4Addr> {
+    let mut addresses = Vec::new();
+    let mut last_leaf = None;
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(addr) = line.strip_prefix("|-- ") {
+            last_leaf = addr.parse::<Ipv4Addr>().ok();
+        } else if line.starts_with("/32 host LOCAL")
+            && let Some(addr) = last_leaf
+            && !addresses.contains(&addr)
+        {
+            addresses.push(addr);
+        }
+    }
+    addresses
+}
+
+fn parse_if_inet6(content: &str) -> Vec<Ipv6Addr> {
+    content
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .filter_map(|hex| u128::from_str_radix(hex, 16).ok())
+        .map(Ipv6Addr::from)
+        .collect()
+}
+
+#[cfg(unix)]
+fn addresses_from_libc() -> Vec<IpAddr> {
+    let mut addresses = Vec::new();
+    // SAFETY: getifaddrs allocates a linked list that we only read and then
+    // release with freeifaddrs; sockaddr pointers are cast according to their
+    // reported family.
+    unsafe {
+        let mut head: *mut libc::ifaddrs = std::ptr::null_mut();
+        if libc::getifaddrs(&mut head) != 0 {
+            return addresses;
+        }
+        let mut cursor = head;
+        while !cursor.is_null() {
+            let addr = (*cursor).ifa_addr;
+            if !addr.is_null() {
+                match (*addr).sa_family as i32 {
+                    libc::AF_INET => {
+                        let sin = &*(addr as *const libc::sockaddr_in);
+                        addresses.push(IpAddr::V4(Ipv4Addr::from(u32::from_be(
+                            sin.sin_addr.s_addr,
+                        ))));
+                    }
+                    libc::AF_INET6 => {
+                        let sin6 = &*(addr as *const libc::sockaddr_in6);
+                        addresses.push(IpAddr::V6(Ipv6Addr::from(sin6.sin6_addr.s6_addr)));
+                    }
+                    _ => {}
+                }
+            }
+            cursor = (*cursor).ifa_next;
+        }
+        libc::freeifaddrs(head);
+    }
+    addresses
+}
+
+#[cfg(not(unix))]
+fn addresses_from_libc() -> Vec<IpAddr> {
+    Vec::new()
+}
+
+/// A CIDR range such as "10.0.0.0/8" or "fd00::/8".
+///
+/// A bare address without a prefix length matches only itself.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cidr {
+    network: IpAddr,
+    prefix: u32,
+}
+
+impl Cidr {
+    pub fn parse(cidr: &str) -> Result<Self> {
+        let (network, prefix) = match cidr.split_once('/') {
+            Some((network, prefix)) => (network, Some(prefix)),
+            None => (cidr, None),
+        };
+        let network: IpAddr = network
+            .parse()
+            .with_context(|| format!("Invalid CIDR '{}': bad address", cidr))?;
+        let max_prefix = if network.is_ipv4() { 32 } else { 128 };
+        let prefix: u32 = match prefix {
+            Some(p) => p
+                .parse()
+                .ok()
+                .filter(|p| *p <= max_prefix)
+                .with_context(|| format!("Invalid CIDR '{}': bad prefix length", cidr))?,
+            None => max_prefix,
+        };
+        Ok(Self { network, prefix })
+    }
+
+    /// Whether `addr` falls inside the range; addresses of the other family never do
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        let prefix = self.prefix;
+        match (self.network, addr) {
+            (IpAddr::V4(net), IpAddr::V4(addr)) => {
+                let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+                u32::from(net) & mask == u32::from(addr) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(addr)) => {
+                let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+                u128::from(net) & mask == u128::from(addr) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix)
+    }
+}
+
+/// Interfaces carrying a default route, from `/proc/net/route` and `/proc/net/ipv6_route`
+pub fn default_route_interfaces(root: &Path) -> Vec<String> {
+    let mut interfaces = Vec::new();
+
+    if let Some(route) = read_file(root, "/proc/net/route") {
+        // Iface Destination Gateway Flags RefCnt Use Metric Mask ...
+        for fields in route.lines().skip(1).map(|l| l.split_whitespace().collect::<Vec<_>>()) {
+            if fields.len() > 7
+                && fields[1] == "00000000"
+                && fields[7] == "00000000"
+                && u32::from_str_radix(fields[3], 16).is_ok_and(|flags| flags & RTF_UP != 0)
+            {
+                interfaces.push(fields[0].to_string());
+            }
+        }
+    }
+
+    if let Some(route) = read_file(root, "/proc/net/ipv6_route") {
+        // dest dest_len src src_len next_hop metric refcnt use flags iface
+        for fields in route.lines().map(|l| l.split_whitespace().collect::<Vec<_>>()) {
+            if fields.len() > 9
+                && fields[1] == "00"
+                && fields[0].chars().all(|c| c == '0')
+                && u32::from_str_radix(fields[8], 16)
+                    .is_ok_and(|flags| flags & RTF_UP != 0 && flags & RTF_REJECT == 0)
+            {
+                interfaces.push(fields[9].to_string());
+            }
+        }
+    }
+
+    interfaces.dedup();
+    interfaces
+}
+
+/// Whether a TCP socket is listening on `port`, from `/proc/net/tcp` and `/proc/net/tcp6`
+pub fn port_listening(root: &Path, port: u16) -> bool {
+    ["/proc/net/tcp", "/proc/net/tcp6"]
+        .iter()
+        .filter_map(|path| read_file(root, path))
+        .any(|table| {
+            // sl local_address rem_address st ...
+            table.lines().skip(1).any(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                fields.len() > 3
+                    && fields[3] == TCP_LISTEN
+                    && fields[1]
+                        .rsplit_once(':')
+                        .and_then(|(_, hex)| u16::from_str_radix(hex, 16).ok())
+                        == Some(port)
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::facts::test_utils::create_fixture_root;
+
+    const FIB_TRIE: &str = "Main:
+  +-- 0.0.0.0/0 3 0 5
+     |-- 0.0.0.0
+        /0 universe UNICAST
+     +-- 127.0.0.0/8 2 0 2
+        +-- 127.0.0.0/31 1 0 0
+           |-- 127.0.0.0
+              /8 host LOCAL
+           |-- 127.0.0.1
+              /32 host LOCAL
+     +-- 10.8.0.0/24 2 0 2
+           |-- 10.8.0.7
+              /32 host LOCAL
+        |-- 10.8.0.255
+           /32 link BROADCAST
+Local:
+     +-- 127.0.0.0/8 2 0 2
+           |-- 127.0.0.1
+              /32 host LOCAL
+";
+
+    const IF_INET6: &str = "fd000000000000000000000000000002 04 40 00 82     eth0
+00000000000000000000000000000001 01 80 10 80       lo
+";
+
+    const ROUTE: &str = "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT
+wg0\t00000000\t0108000A\t0003\t0\t0\t0\t00000000\t0\t0\t0
+eth0\t0000A8C0\t00000000\t0001\t0\t0\t0\t00FFFFFF\t0\t0\t0
+";
+
+    const TCP: &str = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:1538 00000000:0000 0A 00000000:00000000 00:00000000 00000000   999        0 871 1
+   1: 0100007F:0CEA 0100007F:1538 01 00000000:00000000 00:00000000 00000000  1000        0 872 1
+";
+
+    #[test]
+    fn test_interface_up() -> Result<()> {
+        let root = create_fixture_root(&[
+            ("sys/class/net/eth0/operstate", "up\n"),
+            ("sys/class/net/wg0/operstate", "unknown\n"),
+            ("sys/class/net/wg0/flags", "0x1091\n"),
+            ("sys/class/net/tun0/operstate", "unknown\n"),
+            ("sys/class/net/tun0/flags", "0x1090\n"),
+            ("sys/class/net/wlan0/operstate", "down\n"),
+        ])?;
+        assert!(interface_up(root.path(), "eth0"));
+        assert!(interface_up(root.path(), "wg0"));
+        assert!(!interface_up(root.path(), "tun0"));
+        assert!(!interface_up(root.path(), "wlan0"));
+        assert!(!interface_up(root.path(), "missing0"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_local_addresses() -> Result<()> {
+        let root = create_fixture_root(&[
+            ("proc/net/fib_trie", FIB_TRIE),
+            ("proc/net/if_inet6", IF_INET6),
+        ])?;
+        let addresses = local_addresses(root.path());
+        assert_eq!(
+            addresses,
+            vec![
+                "10.8.0.7".parse::<IpAddr>()?,
+                "127.0.0.1".parse()?,
+                "::1".parse()?,
+                "fd00::2".parse()?,
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cidr_contains() -> Result<()> {
+        let contains =
+            |cidr: &str, addr: &str| -> Result<bool> { Ok(Cidr::parse(cidr)?.contains(addr.parse()?)) };
+        assert!(contains("10.0.0.0/8", "10.8.0.7")?);
+        assert!(!contains("10.0.0.0/8", "192.168.1.1")?);
+        assert!(contains("0.0.0.0/0", "192.168.1.1")?);
+        assert!(contains("10.8.0.7", "10.8.0.7")?);
+        assert!(contains("fd00::/8", "fd00::2")?);
+        assert!(!contains("fd00::/8", "10.8.0.7")?);
+        assert!(Cidr::parse("10.0.0.0/33").is_err());
+        assert!(Cidr::parse("corp/8").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_route_interfaces() -> Result<()> {
+        let ipv6_route = "00000000000000000000000000000000 00 00000000000000000000000000000000 00 fd000000000000000000000000000001 00000400 00000001 00000000 00000003     eth0
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 00000000000000000000000000000000 ffffffff 00000001 00000000 00200200       lo
+";
+        let root = create_fixture_root(&[
+            ("proc/net/route", ROUTE),
+            ("proc/net/ipv6_route", ipv6_route),
+        ])?;
+        assert_eq!(default_route_interfaces(root.path()), vec!["wg0", "eth0"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_port_listening() -> Result<()> {
+        let root = create_fixture_root(&[("proc/net/tcp", TCP)])?;
+        assert!(port_listening(root.path(), 5432));
+        // 3306 only appears as a connected (not listening) local port
+        assert!(!port_listening(root.path(), 3306));
+        assert!(!port_listening(root.path(), 8080));
+        Ok(())
+    }
+}
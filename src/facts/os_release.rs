@@ -0,0 +1,108 @@
+use std::path::Path;
+
+use crate::facts::read_file;
+
+/// Fields of interest from `os-release(5)`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OsRelease {
+    pub id: Option<String>,
+    pub id_like: Vec<String>,
+    pub version_id: Option<String>,
+}
+
+/// Read `/etc/os-release`, falling back to `/usr/lib/os-release`
+pub fn read(root: &Path) -> Option<OsRelease> {
+    read_file(root, "/etc/os-release")
+        .or_else(|| read_file(root, "/usr/lib/os-release"))
+        .map(|content| parse(&content))
+}
+
+fn parse(content: &str) -> OsRelease {
+    let mut release = OsRelease::default();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = unquote(value);
+        match key {
+            "ID" => release.id = Some(value.to_lowercase()),
+            "ID_LIKE" => {
+                release.id_like = value.split_whitespace().map(|s| s.to_lowercase()).collect()
+            }
+            "VERSION_ID" => release.version_id = Some(value),
+            _ => {}
+        }
+    }
+    release
+}
+
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    let stripped = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+        .unwrap_or(value);
+    stripped.replace("\\\"", "\"").replace("\\\\", "\\")
+}
+
+/// Running kernel release from `/proc/sys/kernel/osrelease` (e.g. "6.5.0-35-generic")
+pub fn kernel_version(root: &Path) -> Option<String> {
+    read_file(root, "/proc/sys/kernel/osrelease").map(|s| s.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::facts::test_utils::create_fixture_root;
+
+    const UBUNTU: &str = r#"PRETTY_NAME="Ubuntu 22.04.4 LTS"
+NAME="Ubuntu"
+VERSION_ID="22.04"
+ID=ubuntu
+ID_LIKE=debian
+"#;
+
+    #[test]
+    fn test_read_etc_os_release() -> Result<()> {
+        let root = create_fixture_root(&[("etc/os-release", UBUNTU)])?;
+        let release = read(root.path()).unwrap();
+        assert_eq!(release.id.as_deref(), Some("ubuntu"));
+        assert_eq!(release.id_like, vec!["debian".to_string()]);
+        assert_eq!(release.version_id.as_deref(), Some("22.04"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_usr_lib_fallback() -> Result<()> {
+        let root = create_fixture_root(&[(
+            "usr/lib/os-release",
+            "ID=\"rocky\"\nID_LIKE=\"rhel centos fedora\"\nVERSION_ID='9.3'\n",
+        )])?;
+        let release = read(root.path()).unwrap();
+        assert_eq!(release.id.as_deref(), Some("rocky"));
+        assert_eq!(release.id_like, vec!["rhel", "centos", "fedora"]);
+        assert_eq!(release.version_id.as_deref(), Some("9.3"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_missing() -> Result<()> {
+        let root = create_fixture_root(&[])?;
+        assert!(read(root.path()).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_kernel_version() -> Result<()> {
+        let root = create_fixture_root(&[("proc/sys/kernel/osrelease", "6.5.0-35-generic\n")])?;
+        assert_eq!(kernel_version(root.path()).as_deref(), Some("6.5.0-35-generic"));
+        Ok(())
+    }
+}
@@ -0,0 +1,191 @@
+//! Path expansion and file predicates shared by every path-taking DSL function.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use tracing::debug;
+
+/// Expand `~` and `$VAR`/`${VAR}` in `path`, then resolve a relative result against `base`.
+///
+/// Returns `None` when a referenced variable is unset (or `~` can't be
+/// resolved), since such a path can't name anything on this machine.
+pub fn expand(path: &str, base: Option<&Path>) -> Option<PathBuf> {
+    expand_with(path, base, |name| std::env::var(name).ok(), dirs::home_dir)
+}
+
+fn expand_with(
+    path: &str,
+    base: Option<&Path>,
+    env: impl Fn(&str) -> Option<String>,
+    home: impl Fn() -> Option<PathBuf>,
+) -> Option<PathBuf> {
+    let mut expanded = String::new();
+
+    let rest = if path == "~" || path.starts_with("~/") {
+        let Some(home) = home() else {
+            debug!("Cannot expand '~' in '{}': no home directory", path);
+            return None;
+        };
+        expanded.push_str(&home.to_string_lossy());
+        &path[1..]
+    } else {
+        path
+    };
+
+    let mut chars = rest.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            expanded.push(c);
+            continue;
+        }
+        let braced = chars.peek() == Some(&'{');
+        if braced {
+            chars.next();
+        }
+        let mut name = String::new();
+        while let Some(&c) = chars.peek() {
+            if braced && c == '}' {
+                chars.next();
+                break;
+            }
+            if !(braced || c.is_ascii_alphanumeric() || c == '_') {
+                break;
+            }
+            name.push(c);
+            chars.next();
+        }
+        if name.is_empty() {
+            // A lone '$' is kept literally
+            expanded.push('$');
+            continue;
+        }
+        match env(&name) {
+            Some(value) => expanded.push_str(&value),
+            None => {
+                debug!("Cannot expand '${}' in '{}': variable is not set", name, path);
+                return None;
+            }
+        }
+    }
+
+    let expanded = PathBuf::from(expanded);
+    match base {
+        Some(base) if expanded.is_relative() => Some(base.join(expanded)),
+        _ => Some(expanded),
+    }
+}
+
+pub fn is_symlink(path: &Path) -> bool {
+    path.symlink_metadata()
+        .is_ok_and(|metadata| metadata.file_type().is_symlink())
+}
+
+#[cfg(unix)]
+pub fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+pub fn is_executable(path: &Path) -> bool {
+    path.is_file()
+        && path.extension().is_some_and(|ext| {
+            ["exe", "bat", "cmd", "com"]
+                .iter()
+                .any(|e| ext.eq_ignore_ascii_case(e))
+        })
+}
+
+/// Whether any path matches a glob pattern (already expanded)
+pub fn glob_exists(pattern: &str) -> anyhow::Result<bool> {
+    let mut matches = glob::glob(pattern)
+        .map_err(|e| anyhow::anyhow!("Invalid glob pattern '{}': {}", pattern, e))?;
+    Ok(matches.any(|entry| entry.is_ok()))
+}
+
+/// Whether `path` was modified less than `seconds` ago
+pub fn modified_within(path: &Path, seconds: u64) -> bool {
+    path.metadata()
+        .and_then(|metadata| metadata.modified())
+        .is_ok_and(|modified| {
+            SystemTime::now()
+                .duration_since(modified)
+                .map_or(true, |age| age < Duration::from_secs(seconds))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::facts::test_utils::create_fixture_root;
+
+    fn expand_test(path: &str, base: Option<&Path>) -> Option<PathBuf> {
+        expand_with(
+            path,
+            base,
+            |name| match name {
+                "HOME" => Some("/home/alice".to_string()),
+                "WORK" => Some("/srv/work".to_string()),
+                _ => None,
+            },
+            || Some(PathBuf::from("/home/alice")),
+        )
+    }
+
+    #[test]
+    fn test_expand() {
+        assert_eq!(expand_test("~/.work-machine", None), Some("/home/alice/.work-machine".into()));
+        assert_eq!(expand_test("~", None), Some("/home/alice".into()));
+        assert_eq!(expand_test("$HOME/.ssh", None), Some("/home/alice/.ssh".into()));
+        assert_eq!(expand_test("${WORK}_old/x", None), Some("/srv/work_old/x".into()));
+        assert_eq!(expand_test("/cost/$", None), Some("/cost/$".into()));
+        assert_eq!(expand_test("$UNSET/x", None), None);
+        // ~user isn't expanded
+        assert_eq!(expand_test("~bob/x", None), Some("~bob/x".into()));
+    }
+
+    #[test]
+    fn test_expand_relative_to_base() {
+        let base = Path::new("/cfg/gatekeepers/work");
+        assert_eq!(expand_test("marker", Some(base)), Some("/cfg/gatekeepers/work/marker".into()));
+        assert_eq!(expand_test("/etc/hosts", Some(base)), Some("/etc/hosts".into()));
+        assert_eq!(expand_test("~/x", Some(base)), Some("/home/alice/x".into()));
+    }
+
+    #[test]
+    fn test_file_predicates() -> Result<()> {
+        let root = create_fixture_root(&[("bin/tool", "#!/bin/sh\n"), ("data.txt", "x")])?;
+        let tool = root.path().join("bin/tool");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&tool, std::fs::Permissions::from_mode(0o755))?;
+            std::os::unix::fs::symlink(&tool, root.path().join("link"))?;
+            assert!(is_executable(&tool));
+            assert!(!is_executable(&root.path().join("data.txt")));
+            assert!(is_symlink(&root.path().join("link")));
+        }
+        assert!(!is_symlink(&tool));
+        assert!(!is_executable(&root.path().join("bin")));
+
+        assert!(modified_within(&tool, 3600));
+        assert!(!modified_within(&root.path().join("missing"), 3600));
+        Ok(())
+    }
+
+    #[test]
+    fn test_glob_exists() -> Result<()> {
+        let root = create_fixture_root(&[(".ssh/id_ed25519", ""), (".ssh/config", "")])?;
+        let base = glob::Pattern::escape(&root.path().to_string_lossy());
+        assert!(glob_exists(&format!("{}/.ssh/id_*", base))?);
+        assert!(!glob_exists(&format!("{}/.ssh/id_rsa*", base))?);
+        assert!(glob_exists("[").is_err());
+        Ok(())
+    }
+}
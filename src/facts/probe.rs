@@ -0,0 +1,215 @@
+//! Active local probes (socket connects, commands) with strict timeouts.
+//!
+//! Results are memoized for the lifetime of the process, so one `dotgk sync`
+//! probes each target at most once no matter how many gatekeepers ask.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::io::Read;
+use std::net::TcpStream;
+use std::net::ToSocketAddrs;
+use std::process::Command;
+use std::process::Stdio;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+use std::sync::mpsc;
+use std::time::Duration;
+use std::time::Instant;
+
+use tracing::debug;
+
+static MEMO: LazyLock<Mutex<HashMap<String, Box<dyn Any + Send>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Return the memoized value for `key`, computing it with `compute` on first use
+pub fn memoize<T: Clone + Send + 'static>(key: &str, compute: impl FnOnce() -> T) -> T {
+    if let Some(value) = MEMO
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(key)
+        .and_then(|value| value.downcast_ref::<T>())
+    {
+        debug!("Using memoized result for {}", key);
+        return value.clone();
+    }
+
+    let value = compute();
+    MEMO.lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(key.to_string(), Box::new(value.clone()));
+    value
+}
+
+/// Run `probe` on a helper thread and give up after `timeout`.
+///
+/// Bounds the whole probe, including name resolution, which the socket-level
+/// timeouts don't cover. A probe that overruns is abandoned, not killed.
+pub fn with_timeout(timeout: Duration, probe: impl FnOnce() -> bool + Send + 'static) -> bool {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(probe());
+    });
+    rx.recv_timeout(timeout).unwrap_or(false)
+}
+
+/// Whether a TCP connection to `host:port` succeeds within `timeout`
+pub fn tcp_connect(host: &str, port: u16, timeout: Duration) -> bool {
+    let host = host.to_string();
+    with_timeout(timeout, move || {
+        let Ok(addrs) = (host.as_str(), port).to_socket_addrs() else {
+            return false;
+        };
+        addrs
+            .into_iter()
+            .any(|addr| TcpStream::connect_timeout(&addr, timeout).is_ok())
+    })
+}
+
+/// Whether something accepts connections on the Unix socket at `path` within `timeout`
+#[cfg(unix)]
+pub fn unix_socket_alive(path: &std::path::Path, timeout: Duration) -> bool {
+    let path = path.to_path_buf();
+    with_timeout(timeout, move || {
+        std::os::unix::net::UnixStream::connect(&path).is_ok()
+    })
+}
+
+#[cfg(not(unix))]
+pub fn unix_socket_alive(_path: &std::path::Path, _timeout: Duration) -> bool {
+    false
+}
+
+/// Run `program` with `args` and return its stdout and stderr, or `None` if it
+/// can't be started or doesn't exit within `timeout` (it is killed if it overruns).
+/// Output still held open past `timeout`, e.g. by a background child, is abandoned.
+pub fn command_output(program: &str, args: &[String], timeout: Duration) -> Option<(String, String)> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .inspect_err(|e| debug!("Failed to run '{}': {}", program, e))
+        .ok()?;
+
+    // Drain both pipes on helper threads so a chatty command can't block on a full pipe
+    let drain = |pipe: Option<Box<dyn Read + Send>>| {
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut output = String::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_string(&mut output);
+            }
+            let _ = tx.send(output);
+        });
+        rx
+    };
+    let stdout = drain(child.stdout.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+    let stderr = drain(child.stderr.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => break,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(10)),
+            _ => {
+                debug!("'{}' did not exit within {:?}, killing it", program, timeout);
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    }
+
+    // Anything it left running can keep the pipes open, so the reads share the deadline
+    let read = |rx: mpsc::Receiver<String>| {
+        rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            .inspect_err(|_| debug!("'{}' kept its output open past {:?}", program, timeout))
+            .ok()
+    };
+    Some((read(stdout)?, read(stderr)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use anyhow::Result;
+
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_millis(500);
+
+    #[test]
+    fn test_tcp_connect() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let port = listener.local_addr()?.port();
+        assert!(tcp_connect("127.0.0.1", port, TIMEOUT));
+
+        drop(listener);
+        assert!(!tcp_connect("127.0.0.1", port, TIMEOUT));
+        Ok(())
+    }
+
+    #[test]
+    fn test_with_timeout_gives_up() {
+        let slow = with_timeout(Duration::from_millis(50), || {
+            std::thread::sleep(Duration::from_secs(2));
+            true
+        });
+        assert!(!slow);
+    }
+
+    #[test]
+    fn test_memoize() {
+        let mut calls = 0;
+        let first = memoize("test_memoize", || {
+            calls += 1;
+            true
+        });
+        let second = memoize("test_memoize", || {
+            calls += 1;
+            false
+        });
+        assert!(first && second);
+        assert_eq!(calls, 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_output() {
+        let args = vec!["-c".to_string(), "echo out; echo err >&2".to_string()];
+        assert_eq!(
+            command_output("sh", &args, Duration::from_secs(5)),
+            Some(("out\n".to_string(), "err\n".to_string()))
+        );
+        assert_eq!(command_output("dotgk-no-such-command", &[], TIMEOUT), None);
+
+        let slow = vec!["-c".to_string(), "sleep 5".to_string()];
+        let started = Instant::now();
+        assert_eq!(command_output("sh", &slow, Duration::from_millis(100)), None);
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        // A background child holding stdout open doesn't outlive the timeout
+        let detached = vec!["-c".to_string(), "sleep 5 & echo out".to_string()];
+        let started = Instant::now();
+        assert_eq!(command_output("sh", &detached, Duration::from_millis(300)), None);
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unix_socket_alive() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let path = dir.path().join("agent.sock");
+        assert!(!unix_socket_alive(&path, TIMEOUT));
+
+        let listener = std::os::unix::net::UnixListener::bind(&path)?;
+        assert!(unix_socket_alive(&path, TIMEOUT));
+
+        // A stale socket file with nobody listening is not alive
+        drop(listener);
+        assert!(!unix_socket_alive(&path, TIMEOUT));
+        Ok(())
+    }
+}
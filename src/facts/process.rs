@@ -0,0 +1,126 @@
+//! Running processes, read from `/proc/<pid>/comm` and `/proc/<pid>/cmdline`.
+
+use std::path::Path;
+use std::time::Duration;
+
+use crate::facts::read_file;
+use crate::facts::resolve;
+
+/// Whether a process named `name` is running.
+///
+/// Matches the kernel's `comm` (truncated to 15 bytes), the basename of the
+/// command's `argv[0]`, or for interpreters like `python3 foo.py` the script's
+/// basename, so long names and scripts match too.
+/// Falls back to `ps` where `/proc` isn't available, as on macOS.
+pub fn process_running(root: &Path, name: &str) -> bool {
+    let Ok(entries) = std::fs::read_dir(resolve(root, "/proc")) else {
+        return names_from_ps().iter().any(|candidate| candidate == name);
+    };
+
+    entries.flatten().any(|entry| {
+        let pid = entry.file_name();
+        let Some(pid) = pid.to_str().filter(|pid| pid.bytes().all(|b| b.is_ascii_digit())) else {
+            return false;
+        };
+        let comm = read_file(root, &format!("/proc/{}/comm", pid));
+        if comm.as_deref().map(str::trim_end) == Some(name) {
+            return true;
+        }
+        read_file(root, &format!("/proc/{}/cmdline", pid))
+            .is_some_and(|cmdline| {
+                argv0_basename(&cmdline) == Some(name) || script_basename(&cmdline) == Some(name)
+            })
+    })
+}
+
+/// Interpreters whose first non-option argument is the script they run
+const INTERPRETERS: &[&str] = &[
+    "python", "perl", "ruby", "node", "lua", "php", "bash", "sh", "dash", "zsh",
+];
+
+fn argv0_basename(cmdline: &str) -> Option<&str> {
+    let argv0 = cmdline.split('\0').next().filter(|arg| !arg.is_empty())?;
+    // Daemons that rewrite their title ("sshd: user@pts/0") keep the name first
+    let argv0 = argv0.split_whitespace().next()?;
+    Some(argv0.rsplit('/').next().unwrap_or(argv0).trim_end_matches(':'))
+}
+
+/// The basename of the script an interpreter is running, e.g. "foo.py" for `python3 foo.py`
+fn script_basename(cmdline: &str) -> Option<&str> {
+    let interpreter = argv0_basename(cmdline)?;
+    // Versioned binaries like "python3.12" and "lua5.4" count as their interpreter
+    let interpreter = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    if !INTERPRETERS.contains(&interpreter) {
+        return None;
+    }
+    let script = cmdline
+        .split('\0')
+        .skip(1)
+        .find(|arg| !arg.is_empty() && !arg.starts_with('-'))?;
+    Some(script.rsplit('/').next().unwrap_or(script))
+}
+
+fn names_from_ps() -> Vec<String> {
+    let args = vec!["-axo".to_string(), "comm=".to_string()];
+    crate::facts::probe::command_output("ps", &args, Duration::from_secs(2))
+        .map(|(stdout, _)| {
+            stdout
+                .lines()
+                .map(|line| line.trim().rsplit('/').next().unwrap_or("").to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::facts::test_utils::create_fixture_root;
+
+    #[test]
+    fn test_process_running() -> Result<()> {
+        let root = create_fixture_root(&[
+            ("proc/1/comm", "systemd\n"),
+            ("proc/1/cmdline", "/sbin/init\0splash\0"),
+            ("proc/812/comm", "tailscaled\n"),
+            ("proc/812/cmdline", "/usr/sbin/tailscaled\0--state=/var/lib/tailscale\0"),
+            ("proc/900/comm", "very-long-daem\n"),
+            ("proc/900/cmdline", "/opt/bin/very-long-daemon-name\0"),
+            ("proc/901/comm", "sshd\n"),
+            ("proc/901/cmdline", "sshd: alice@pts/0\0"),
+            ("proc/902/comm", "python3\n"),
+            ("proc/902/cmdline", "/usr/bin/python3\0-u\0/opt/tools/sync-notes.py\0--watch\0"),
+            ("proc/self/comm", "ignored\n"),
+            ("proc/meminfo", ""),
+        ])?;
+        assert!(process_running(root.path(), "tailscaled"));
+        assert!(process_running(root.path(), "systemd"));
+        assert!(process_running(root.path(), "init"));
+        assert!(process_running(root.path(), "very-long-daemon-name"));
+        assert!(process_running(root.path(), "sshd"));
+        assert!(process_running(root.path(), "python3"));
+        assert!(process_running(root.path(), "sync-notes.py"));
+        assert!(!process_running(root.path(), "--watch"));
+        assert!(!process_running(root.path(), "tailscale"));
+        assert!(!process_running(root.path(), "ignored"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_argv0_basename() {
+        assert_eq!(argv0_basename("/usr/bin/python3\0script.py\0"), Some("python3"));
+        assert_eq!(argv0_basename("sshd: alice [priv]\0"), Some("sshd"));
+        assert_eq!(argv0_basename(""), None);
+    }
+
+    #[test]
+    fn test_script_basename() {
+        assert_eq!(script_basename("/usr/bin/python3\0foo.py\0"), Some("foo.py"));
+        assert_eq!(script_basename("python3.12\0-u\0/srv/app/run.py\0"), Some("run.py"));
+        assert_eq!(script_basename("/bin/bash\0/home/alice/bin/backup.sh\0"), Some("backup.sh"));
+        assert_eq!(script_basename("/usr/bin/python3\0"), None);
+        assert_eq!(script_basename("/usr/bin/vim\0notes.txt\0"), None);
+    }
+}
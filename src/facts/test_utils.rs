@@ -0,0 +1,21 @@
+#[cfg(test)]
+use std::fs;
+
+#[cfg(test)]
+use anyhow::Result;
+#[cfg(test)]
+use tempfile::TempDir;
+
+/// Build a fake filesystem root from `(relative path, content)` pairs
+#[cfg(test)]
+pub fn create_fixture_root(files: &[(&str, &str)]) -> Result<TempDir> {
+    let root = TempDir::new()?;
+    for (path, content) in files {
+        let path = root.path().join(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, content)?;
+    }
+    Ok(root)
+}
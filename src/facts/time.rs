@@ -0,0 +1,244 @@
+//! Clock and calendar helpers for time-based gatekeepers.
+//!
+//! Every check also reports the next instant at which its answer could flip,
+//! so the caller can expire cached results exactly then.
+
+use anyhow::Context;
+use anyhow::Result;
+use chrono::DateTime;
+use chrono::Days;
+use chrono::FixedOffset;
+use chrono::Local;
+use chrono::NaiveDate;
+use chrono::NaiveDateTime;
+use chrono::NaiveTime;
+use chrono::TimeZone;
+use chrono::Weekday;
+
+/// Source of the current time and timezone.
+///
+/// Defaults to the system clock; `DOTGK_NOW` (RFC 3339) pins it for testing.
+#[derive(Debug, Clone)]
+pub struct Clock {
+    now: DateTime<FixedOffset>,
+    timezone: Option<String>,
+    system: bool,
+}
+
+impl Clock {
+    pub fn system() -> Self {
+        Self {
+            now: Local::now().fixed_offset(),
+            timezone: system_timezone(),
+            system: true,
+        }
+    }
+
+    /// A clock frozen at `now`, reporting `timezone` as the zone name
+    pub fn fixed(now: DateTime<FixedOffset>, timezone: Option<&str>) -> Self {
+        Self {
+            now,
+            timezone: timezone.map(String::from),
+            system: false,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        match std::env::var("DOTGK_NOW") {
+            Ok(now) => match DateTime::parse_from_rfc3339(&now) {
+                Ok(now) => Self::fixed(now, system_timezone().as_deref()),
+                Err(e) => {
+                    tracing::warn!("Ignoring invalid DOTGK_NOW '{}': {}", now, e);
+                    Self::system()
+                }
+            },
+            Err(_) => Self::system(),
+        }
+    }
+
+    pub fn now(&self) -> DateTime<FixedOffset> {
+        self.now
+    }
+
+    pub fn timestamp(&self) -> i64 {
+        self.now.timestamp()
+    }
+
+    pub fn timezone(&self) -> Option<&str> {
+        self.timezone.as_deref()
+    }
+
+    /// Unix timestamp of a local wall-clock time, honoring DST for the system clock
+    fn timestamp_of(&self, local: NaiveDateTime) -> i64 {
+        if self.system
+            && let Some(dt) = Local.from_local_datetime(&local).earliest()
+        {
+            return dt.timestamp();
+        }
+        self.now
+            .offset()
+            .from_local_datetime(&local)
+            .earliest()
+            .map(|dt| dt.timestamp())
+            .unwrap_or_else(|| local.and_utc().timestamp())
+    }
+
+    /// Unix timestamp of the next local midnight
+    pub fn next_midnight(&self) -> i64 {
+        let tomorrow = self.now.date_naive() + Days::new(1);
+        self.timestamp_of(tomorrow.and_time(NaiveTime::MIN))
+    }
+}
+
+/// IANA zone name from `TZ`, falling back to the system configuration
+fn system_timezone() -> Option<String> {
+    std::env::var("TZ")
+        .ok()
+        .map(|tz| tz.trim_start_matches(':').to_string())
+        .filter(|tz| !tz.is_empty())
+        .or_else(|| iana_time_zone::get_timezone().ok())
+}
+
+pub fn parse_weekday(day: &str) -> Result<Weekday> {
+    day.parse::<Weekday>().map_err(|_| {
+        anyhow::anyhow!(
+            "Invalid weekday '{}'. Use mon, tue, wed, thu, fri, sat or sun",
+            day
+        )
+    })
+}
+
+/// Whether `day` falls in the inclusive range `start..=end`, wrapping past Sunday
+pub fn weekday_in_range(day: Weekday, start: Weekday, end: Weekday) -> bool {
+    let (day, start, end) = (
+        day.num_days_from_monday(),
+        start.num_days_from_monday(),
+        end.num_days_from_monday(),
+    );
+    if start <= end {
+        start <= day && day <= end
+    } else {
+        day >= start || day <= end
+    }
+}
+
+pub fn parse_time(time: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(time, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M:%S"))
+        .with_context(|| format!("Invalid time '{}'. Use HH:MM or HH:MM:SS", time))
+}
+
+pub fn parse_date(date: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .with_context(|| format!("Invalid date '{}'. Use YYYY-MM-DD", date))
+}
+
+/// Whether the local time is in `[start, end)`, wrapping past midnight when
+/// `end < start`, plus the timestamp of the next `start` or `end` crossing
+pub fn time_between(clock: &Clock, start: NaiveTime, end: NaiveTime) -> (bool, i64) {
+    let now = clock.now().naive_local();
+    let time = now.time();
+    let inside = if start <= end {
+        start <= time && time < end
+    } else {
+        time >= start || time < end
+    };
+
+    let next_crossing = |at: NaiveTime| {
+        let today = now.date().and_time(at);
+        let next = if today > now {
+            today
+        } else {
+            today + Days::new(1)
+        };
+        clock.timestamp_of(next)
+    };
+    (inside, next_crossing(start).min(next_crossing(end)))
+}
+
+/// Whether the local date is strictly before `date`, plus the timestamp at
+/// which that stops being true (if it hasn't already)
+pub fn date_before(clock: &Clock, date: NaiveDate) -> (bool, Option<i64>) {
+    let before = clock.now().date_naive() < date;
+    let boundary = before.then(|| clock.timestamp_of(date.and_time(NaiveTime::MIN)));
+    (before, boundary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock_at(now: &str) -> Clock {
+        Clock::fixed(
+            DateTime::parse_from_rfc3339(now).unwrap(),
+            Some("America/New_York"),
+        )
+    }
+
+    #[test]
+    fn test_weekday_in_range() -> Result<()> {
+        let (mon, fri, sat, sun) = (
+            parse_weekday("mon")?,
+            parse_weekday("Friday")?,
+            parse_weekday("sat")?,
+            parse_weekday("sun")?,
+        );
+        assert!(weekday_in_range(Weekday::Wed, mon, fri));
+        assert!(!weekday_in_range(sat, mon, fri));
+        // Wrapping range: fri..mon covers the weekend
+        assert!(weekday_in_range(sun, fri, mon));
+        assert!(!weekday_in_range(Weekday::Wed, fri, mon));
+        assert!(parse_weekday("someday").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_time_between() -> Result<()> {
+        // Wednesday 2026-10-14 10:30 in UTC-4
+        let clock = clock_at("2026-10-14T10:30:00-04:00");
+        let (inside, boundary) = time_between(&clock, parse_time("09:00")?, parse_time("18:00")?);
+        assert!(inside);
+        assert_eq!(boundary, clock_at("2026-10-14T18:00:00-04:00").timestamp());
+
+        let (inside, boundary) = time_between(&clock, parse_time("12:00")?, parse_time("13:00")?);
+        assert!(!inside);
+        assert_eq!(boundary, clock_at("2026-10-14T12:00:00-04:00").timestamp());
+
+        // Overnight window
+        let (inside, boundary) = time_between(&clock, parse_time("22:00")?, parse_time("06:00")?);
+        assert!(!inside);
+        assert_eq!(boundary, clock_at("2026-10-14T22:00:00-04:00").timestamp());
+
+        let late = clock_at("2026-10-14T23:15:00-04:00");
+        let (inside, boundary) = time_between(&late, parse_time("22:00")?, parse_time("06:00")?);
+        assert!(inside);
+        assert_eq!(boundary, clock_at("2026-10-15T06:00:00-04:00").timestamp());
+
+        assert!(parse_time("25:00").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_date_before() -> Result<()> {
+        let clock = clock_at("2026-10-14T10:30:00-04:00");
+        let (before, boundary) = date_before(&clock, parse_date("2026-12-31")?);
+        assert!(before);
+        assert_eq!(boundary, Some(clock_at("2026-12-31T00:00:00-04:00").timestamp()));
+
+        let (before, boundary) = date_before(&clock, parse_date("2026-10-14")?);
+        assert!(!before);
+        assert_eq!(boundary, None);
+
+        assert!(parse_date("12/31/2026").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_next_midnight() {
+        let clock = clock_at("2026-10-14T10:30:00-04:00");
+        assert_eq!(
+            clock.next_midnight(),
+            clock_at("2026-10-15T00:00:00-04:00").timestamp()
+        );
+    }
+}
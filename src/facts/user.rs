@@ -0,0 +1,254 @@
+use std::path::Path;
+
+use crate::facts::read_file;
+
+/// Credentials of the current process
+#[derive(Debug, Clone, PartialEq)]
+pub struct Credentials {
+    pub uid: u32,
+    /// Effective uid, which differs from `uid` under setuid or `sudo -E`-style wrappers
+    pub euid: u32,
+    pub gid: u32,
+    pub groups: Vec<u32>,
+}
+
+/// Read the real and effective uid, gid and supplementary groups from `/proc/self/status`,
+/// falling back to the libc calls where `/proc` isn't available
+pub fn current(root: &Path) -> Option<Credentials> {
+    read_file(root, "/proc/self/status")
+        .and_then(|status| parse_status(&status))
+        .or_else(from_libc)
+}
+
+fn parse_status(status: &str) -> Option<Credentials> {
+    let field = |name: &str| {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+            .map(|rest| rest.split_whitespace().collect::<Vec<_>>())
+    };
+
+    // Uid/Gid lines are "real effective saved filesystem"
+    let uids = field("Uid")?;
+    let uid = uids.first()?.parse().ok()?;
+    let euid = uids.get(1)?.parse().ok()?;
+    let gid = field("Gid")?.first()?.parse().ok()?;
+    let groups = field("Groups")
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|g| g.parse().ok())
+        .collect();
+    Some(Credentials {
+        uid,
+        euid,
+        gid,
+        groups,
+    })
+}
+
+#[cfg(unix)]
+fn from_libc() -> Option<Credentials> {
+    // SAFETY: getuid/geteuid/getgid cannot fail, and getgroups writes at most
+    // `len` entries into a buffer of exactly that size.
+    unsafe {
+        let uid = libc::getuid();
+        let euid = libc::geteuid();
+        let gid = libc::getgid();
+        let len = libc::getgroups(0, std::ptr::null_mut());
+        let mut groups = vec![0 as libc::gid_t; len.max(0) as usize];
+        let written = libc::getgroups(len, groups.as_mut_ptr());
+        groups.truncate(written.max(0) as usize);
+        Some(Credentials {
+            uid,
+            euid,
+            gid,
+            groups,
+        })
+    }
+}
+
+#[cfg(not(unix))]
+fn from_libc() -> Option<Credentials> {
+    None
+}
+
+/// Name of the user with `uid`, from `/etc/passwd`
+pub fn username_for_uid(root: &Path, uid: u32) -> Option<String> {
+    let passwd = read_file(root, "/etc/passwd")?;
+    passwd.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split(':').collect();
+        (fields.len() > 2 && fields[2].parse() == Ok(uid)).then(|| fields[0].to_string())
+    })
+}
+
+/// Name of the current user, resolved from the process uid, falling back to `$USER`/`$USERNAME`
+pub fn username(root: &Path) -> Option<String> {
+    current(root)
+        .and_then(|creds| username_for_uid(root, creds.uid))
+        .or_else(|| std::env::var("USER").ok())
+        .or_else(|| std::env::var("USERNAME").ok())
+}
+
+/// A group entry from `/etc/group` or NSS
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group {
+    pub gid: u32,
+    pub members: Vec<String>,
+}
+
+/// Look up a group by name in `/etc/group`, falling back to NSS (`getgrnam_r`)
+/// for groups that come from LDAP, sssd and the like
+pub fn group_by_name(root: &Path, name: &str) -> Option<Group> {
+    group_from_file(root, name).or_else(|| group_from_nss(name))
+}
+
+fn group_from_file(root: &Path, name: &str) -> Option<Group> {
+    let groups = read_file(root, "/etc/group")?;
+    groups.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split(':').collect();
+        if fields.len() < 3 || fields[0] != name {
+            return None;
+        }
+        Some(Group {
+            gid: fields[2].parse().ok()?,
+            members: fields
+                .get(3)
+                .map(|m| m.split(',').filter(|s| !s.is_empty()).map(String::from).collect())
+                .unwrap_or_default(),
+        })
+    })
+}
+
+#[cfg(unix)]
+fn group_from_nss(name: &str) -> Option<Group> {
+    let name = std::ffi::CString::new(name).ok()?;
+    let mut buffer: Vec<libc::c_char> = vec![0; 4096];
+    loop {
+        // SAFETY: `group` and `result` are valid out-pointers, and getgrnam_r only
+        // writes strings into `buffer`, which is `buffer.len()` bytes long and
+        // outlives every read of the pointers it fills in below.
+        unsafe {
+            let mut group: libc::group = std::mem::zeroed();
+            let mut result: *mut libc::group = std::ptr::null_mut();
+            let status = libc::getgrnam_r(
+                name.as_ptr(),
+                &mut group,
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                &mut result,
+            );
+            // Large directory groups don't fit; retry with a bigger buffer
+            if status == libc::ERANGE && buffer.len() < 1 << 20 {
+                buffer.resize(buffer.len() * 2, 0);
+                continue;
+            }
+            if status != 0 || result.is_null() {
+                return None;
+            }
+
+            let mut members = Vec::new();
+            let mut member = group.gr_mem;
+            while !member.is_null() && !(*member).is_null() {
+                members.push(std::ffi::CStr::from_ptr(*member).to_string_lossy().into_owned());
+                member = member.add(1);
+            }
+            return Some(Group {
+                gid: group.gr_gid,
+                members,
+            });
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn group_from_nss(_name: &str) -> Option<Group> {
+    None
+}
+
+/// Whether the current user belongs to the named group, either through the
+/// process credentials or as a listed member of the group
+pub fn in_group(root: &Path, name: &str) -> bool {
+    let Some(group) = group_by_name(root, name) else {
+        return false;
+    };
+    if let Some(creds) = current(root)
+        && (creds.gid == group.gid || creds.groups.contains(&group.gid))
+    {
+        return true;
+    }
+    username(root).is_some_and(|user| group.members.contains(&user))
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::facts::test_utils::create_fixture_root;
+
+    const STATUS: &str = "Name:\tcat\nUid:\t1000\t1000\t1000\t1000\nGid:\t1000\t1000\t1000\t1000\nGroups:\t4 27 1000 \n";
+    const PASSWD: &str = "root:x:0:0:root:/root:/bin/bash\nalice:x:1000:1000:Alice:/home/alice:/bin/zsh\n";
+    const GROUP: &str = "root:x:0:\nadm:x:4:alice\nsudo:x:27:alice\ndocker:x:998:alice\nwheel:x:10:\n";
+
+    #[test]
+    fn test_current_credentials() -> Result<()> {
+        let root = create_fixture_root(&[("proc/self/status", STATUS)])?;
+        let creds = current(root.path()).unwrap();
+        assert_eq!(creds.uid, 1000);
+        assert_eq!(creds.euid, 1000);
+        assert_eq!(creds.gid, 1000);
+        assert_eq!(creds.groups, vec![4, 27, 1000]);
+
+        // A setuid-root process keeps its real uid
+        let setuid = STATUS.replace("Uid:\t1000\t1000", "Uid:\t1000\t0");
+        let root = create_fixture_root(&[("proc/self/status", setuid.as_str())])?;
+        let creds = current(root.path()).unwrap();
+        assert_eq!((creds.uid, creds.euid), (1000, 0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_username() -> Result<()> {
+        let root = create_fixture_root(&[("proc/self/status", STATUS), ("etc/passwd", PASSWD)])?;
+        assert_eq!(username(root.path()).as_deref(), Some("alice"));
+        assert_eq!(username_for_uid(root.path(), 0).as_deref(), Some("root"));
+        assert_eq!(username_for_uid(root.path(), 4242), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_in_group() -> Result<()> {
+        let root = create_fixture_root(&[
+            ("proc/self/status", STATUS),
+            ("etc/passwd", PASSWD),
+            ("etc/group", GROUP),
+        ])?;
+        // Supplementary group of the process
+        assert!(in_group(root.path(), "sudo"));
+        // Listed member only (e.g. added since the session started)
+        assert!(in_group(root.path(), "docker"));
+        assert!(!in_group(root.path(), "wheel"));
+        assert!(!in_group(root.path(), "no-such-group"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_group_by_name() -> Result<()> {
+        let root = create_fixture_root(&[("etc/group", GROUP)])?;
+        let group = group_by_name(root.path(), "docker").unwrap();
+        assert_eq!(group.gid, 998);
+        assert_eq!(group.members, vec!["alice".to_string()]);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_group_from_nss() -> Result<()> {
+        // Every unix system resolves gid 0's group through NSS, whatever it's called
+        let root_group = if cfg!(target_os = "linux") { "root" } else { "wheel" };
+        let empty = create_fixture_root(&[])?;
+        assert_eq!(group_by_name(empty.path(), root_group).map(|group| group.gid), Some(0));
+        assert_eq!(group_by_name(empty.path(), "no-such-group-12345"), None);
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::sync::LazyLock;
+
+use anyhow::Result;
+use regex::Regex;
+
+/// A parsed semver or loose dotted version ("0.10.2", "22.04", "v2.43.0-rc1").
+///
+/// Numeric components compare with missing ones treated as 0, so "1.2" equals
+/// "1.2.0". A pre-release sorts before its release, and build metadata is ignored.
+#[derive(Debug, Clone)]
+pub struct Version {
+    pub components: Vec<u64>,
+    pub pre: Option<String>,
+    original: String,
+}
+
+impl Version {
+    pub fn parse(version: &str) -> Result<Self> {
+        let original = version.trim();
+        let rest = original.trim_start_matches(['v', 'V']);
+        let rest = rest.split('+').next().unwrap_or(rest);
+        let (core, mut pre) = match rest.split_once('-') {
+            Some((core, pre)) => (core, Some(pre.to_string())),
+            None => (rest, None),
+        };
+
+        let mut components = Vec::new();
+        let parts: Vec<&str> = core.split('.').collect();
+        for (i, part) in parts.iter().enumerate() {
+            let digits: String = part.chars().take_while(|c| c.is_ascii_digit()).collect();
+            let Ok(n) = digits.parse::<u64>() else {
+                anyhow::bail!("Invalid version '{}'", version);
+            };
+            components.push(n);
+            // "1.2.3rc1": letters after the last component start a pre-release
+            if digits.len() != part.len() {
+                if i + 1 != parts.len() || pre.is_some() {
+                    anyhow::bail!("Invalid version '{}'", version);
+                }
+                pre = Some(part[digits.len()..].to_string());
+            }
+        }
+
+        if pre.as_deref() == Some("") {
+            anyhow::bail!("Invalid version '{}'", version);
+        }
+
+        Ok(Self {
+            components,
+            pre,
+            original: original.to_string(),
+        })
+    }
+
+    /// Component `index` (0 = major), or 0 if the version has fewer components
+    pub fn component(&self, index: usize) -> u64 {
+        self.components.get(index).copied().unwrap_or(0)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.original)
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let len = self.components.len().max(other.components.len());
+        for i in 0..len {
+            match self.component(i).cmp(&other.component(i)) {
+                Ordering::Equal => continue,
+                other => return other,
+            }
+        }
+        match (&self.pre, &other.pre) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(_), None) => Ordering::Less,
+            (Some(a), Some(b)) => compare_pre_release(a, b),
+        }
+    }
+}
+
+/// Semver pre-release precedence: numeric identifiers compare numerically
+/// and sort before alphanumeric ones, and a shorter prefix sorts first
+fn compare_pre_release(a: &str, b: &str) -> Ordering {
+    let mut a = a.split('.');
+    let mut b = b.split('.');
+    loop {
+        let ordering = match (a.next(), b.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => match (x.parse::<u64>(), y.parse::<u64>()) {
+                (Ok(x), Ok(y)) => x.cmp(&y),
+                (Ok(_), Err(_)) => Ordering::Less,
+                (Err(_), Ok(_)) => Ordering::Greater,
+                (Err(_), Err(_)) => x.cmp(y),
+            },
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+static VERSION_TOKEN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b[vV]?(\d+(?:\.\d+)+(?:-[0-9A-Za-z][0-9A-Za-z.-]*)?)").unwrap()
+});
+
+/// Find the first dotted version in free-form text such as `--version` output.
+///
+/// "NVIM v0.10.2" -> "0.10.2", "git version 2.43.0" -> "2.43.0".
+pub fn extract(text: &str) -> Option<&str> {
+    VERSION_TOKEN
+        .captures(text)
+        .and_then(|captures| captures.get(1))
+        .map(|m| m.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(version: &str) -> Version {
+        Version::parse(version).unwrap()
+    }
+
+    #[test]
+    fn test_version_parse() {
+        assert_eq!(v("v0.10.2").components, vec![0, 10, 2]);
+        assert_eq!(v("1.2.3-rc.1+build.5").pre.as_deref(), Some("rc.1"));
+        assert_eq!(v("1.2.3rc1").pre.as_deref(), Some("rc1"));
+        assert_eq!(v("22.04").to_string(), "22.04");
+        assert!(Version::parse("").is_err());
+        assert!(Version::parse("1.x.3").is_err());
+        assert!(Version::parse("1.2-").is_err());
+        assert!(Version::parse("nightly").is_err());
+    }
+
+    #[test]
+    fn test_version_ordering() {
+        assert!(v("0.10.0") > v("0.9.5"));
+        assert_eq!(v("1.2"), v("1.2.0"));
+        assert!(v("2.0.0-rc.1") < v("2.0.0"));
+        assert!(v("2.0.0-alpha") < v("2.0.0-alpha.1"));
+        assert!(v("2.0.0-alpha.2") < v("2.0.0-alpha.10"));
+        assert!(v("2.0.0-1") < v("2.0.0-alpha"));
+        assert_eq!(v("1.0.0+a"), v("1.0.0+b"));
+    }
+
+    #[test]
+    fn test_extract() {
+        assert_eq!(extract("NVIM v0.10.2\nBuild type: Release"), Some("0.10.2"));
+        assert_eq!(extract("git version 2.43.0"), Some("2.43.0"));
+        assert_eq!(extract("tmux 3.4"), Some("3.4"));
+        assert_eq!(extract("Python 3.13.0rc2"), Some("3.13.0"));
+        assert_eq!(extract("openjdk version \"21.0.2-ea\" 2024-01-16"), Some("21.0.2-ea"));
+        assert_eq!(extract("no version here 42"), None);
+    }
+}
@@ -1,7 +1,12 @@
 use anyhow::Context;
 use anyhow::Result;
+use std::collections::BTreeMap;
 
+use crate::cache::CacheEntry;
+use crate::hysteresis::Pending;
 use crate::lua_executor::LuaExecutor;
+use crate::lua_executor::SCRIPT_LOG_TARGET;
+use crate::value::GatekeeperValue;
 
 #[cfg(not(test))]
 pub fn get_config_dir() -> Result<std::path::PathBuf> {
@@ -20,11 +25,37 @@ pub fn get_config_dir() -> Result<std::path::PathBuf> {
 
 #[derive(Debug, Clone)]
 pub struct GatekeeperResult {
-    pub value: bool,
+    pub value: GatekeeperValue,
     pub ttl: Option<u64>,
+    /// Files loaded through `require`; a change to any of them invalidates the result
+    pub dependencies: Vec<std::path::PathBuf>,
+    /// Why the gatekeeper returned its value, if it said
+    pub reason: Option<String>,
+    /// Cache entries read with `cached()`/`is_set()` and the values seen (`None` if unset)
+    pub cache_dependencies: std::collections::BTreeMap<String, Option<GatekeeperValue>>,
+    /// A new value held back by `-- debounce:`
+    pub pending: Option<Pending>,
+    /// When the value last changed, tracked for `-- hold_true:`
+    pub changed_at: Option<u64>,
 }
 
+/// Variables passed with `--var key=value`, exposed to Lua as the `args` table
+pub type Variables = BTreeMap<String, String>;
+
+/// Evaluate a gatekeeper without variables or a previous entry
+// Part of the library API; the binary always passes variables
+#[allow(dead_code)]
 pub fn load_and_evaluate_gatekeeper(name: &str) -> Result<GatekeeperResult> {
+    load_and_evaluate_gatekeeper_with_previous(name, None, &Variables::new())
+}
+
+/// Evaluate a gatekeeper whose last cached entry was `previous`, for `previous()`
+/// and debounce/hold headers, with `vars` in its `args` table
+pub fn load_and_evaluate_gatekeeper_with_previous(
+    name: &str,
+    previous: Option<&CacheEntry>,
+    vars: &Variables,
+) -> Result<GatekeeperResult> {
     // Auto-detect if we're loading an init.lua file and extract parent directory
     let gatekeeper_path = get_gatekeeper_path(name)?;
 
@@ -41,10 +72,15 @@ pub fn load_and_evaluate_gatekeeper(name: &str) -> Result<GatekeeperResult> {
         None
     };
 
-    load_and_evaluate_gatekeeper_with_context(name, current_dir)
+    load_and_evaluate_gatekeeper_with_context(name, current_dir, previous, vars)
 }
 
-pub fn load_and_evaluate_gatekeeper_with_context(name: &str, current_dir: Option<String>) -> Result<GatekeeperResult> {
+pub fn load_and_evaluate_gatekeeper_with_context(
+    name: &str,
+    current_dir: Option<String>,
+    previous: Option<&CacheEntry>,
+    vars: &Variables,
+) -> Result<GatekeeperResult> {
     let gatekeeper_path = get_gatekeeper_path(name)
         .with_context(|| format!("Failed to get gatekeeper path for '{}'", name))?;
 
@@ -63,17 +99,39 @@ pub fn load_and_evaluate_gatekeeper_with_context(name: &str, current_dir: Option
     let executor = LuaExecutor::new()
         .context("Failed to create Lua executor")?;
 
+    // Errors and tracebacks point at the gatekeeper's file
+    executor.set_source(name, &gatekeeper_path);
+
+    // Resolve relative paths in file checks against the gatekeeper's own directory
+    if let Some(dir) = gatekeeper_path.parent() {
+        executor.set_script_dir(dir);
+    }
+
     // Set current directory context if provided (for init.lua files)
     if let Some(dir) = current_dir {
         executor.set_current_dir(&dir)?;
     }
 
+    if let Some(entry) = previous {
+        executor.set_previous(entry.into());
+    }
+
+    executor.set_args(vars)?;
+
+    // Script logs carry the name of the gatekeeper they came from
+    let _span = tracing::info_span!(target: SCRIPT_LOG_TARGET, "gatekeeper", name).entered();
+
     let result = executor.execute(&script)
         .with_context(|| format!("Failed to execute Lua gatekeeper '{}'", name))?;
 
     Ok(GatekeeperResult {
         value: result.value,
         ttl: result.ttl,
+        dependencies: result.dependencies,
+        reason: result.reason,
+        cache_dependencies: result.cache_dependencies,
+        pending: result.pending,
+        changed_at: result.changed_at,
     })
 }
 
@@ -111,6 +169,110 @@ pub fn get_gatekeeper_path(name: &str) -> Result<std::path::PathBuf> {
     }
 }
 
+/// Path of a shared module in `lib/` ("corp/hosts" -> lib/corp/hosts.lua or
+/// lib/corp/hosts/init.lua), or `None` if there is no such module.
+///
+/// Modules in `lib/` can be required like gatekeepers but may return any value
+/// and are never synced themselves.
+pub fn get_lib_path(name: &str) -> Result<Option<std::path::PathBuf>> {
+    let mut lib_dir = get_config_dir()?;
+    lib_dir.push("lib");
+
+    let candidates = [
+        lib_dir.join(format!("{}.lua", name)),
+        lib_dir.join(name).join("init.lua"),
+    ];
+    Ok(candidates.into_iter().find(|path| path.is_file()))
+}
+
+/// Characters percent-encoded in variable values, so different variables never share a key
+const KEY_RESERVED: &[char] = &['%', ',', '=', '{', '}'];
+
+/// Cache key for a gatekeeper evaluated with variables, e.g. `project/is_work{dir=/a,profile=ci}`
+// Only the binary evaluates with variables from the command line
+#[allow(dead_code)]
+pub fn cache_key(name: &str, vars: &Variables) -> String {
+    if vars.is_empty() {
+        return name.to_string();
+    }
+    let vars: Vec<String> = vars
+        .iter()
+        .map(|(key, value)| {
+            let value: String = value
+                .chars()
+                .map(|c| {
+                    if KEY_RESERVED.contains(&c) {
+                        format!("%{:02X}", c as u32)
+                    } else {
+                        c.to_string()
+                    }
+                })
+                .collect();
+            format!("{}={}", key, value)
+        })
+        .collect();
+    format!("{}{{{}}}", name, vars.join(","))
+}
+
+/// Split a cache key back into the gatekeeper name and its variables,
+/// or `None` if the variables aren't in the form `cache_key` writes
+pub fn parse_cache_key(key: &str) -> Option<(&str, Variables)> {
+    let name = base_name(key);
+    let Some(vars) = key[name.len()..].strip_prefix('{').and_then(|rest| rest.strip_suffix('}')) else {
+        return Some((name, Variables::new()));
+    };
+
+    let mut parsed = Variables::new();
+    for var in vars.split(',') {
+        let (var, value) = var.split_once('=')?;
+        let mut decoded = String::new();
+        let mut chars = value.chars();
+        while let Some(c) = chars.next() {
+            if c == '%' {
+                let hex: String = chars.by_ref().take(2).collect();
+                decoded.push(char::from(u8::from_str_radix(&hex, 16).ok()?));
+            } else {
+                decoded.push(c);
+            }
+        }
+        parsed.insert(var.to_string(), decoded);
+    }
+    Some((name, parsed))
+}
+
+/// The gatekeeper name in a cache key, without any variables
+pub fn base_name(key: &str) -> &str {
+    match key.find('{') {
+        Some(index) if key.ends_with('}') => &key[..index],
+        _ => key,
+    }
+}
+
+/// Whether a gatekeeper is an internal building block: any path component
+/// starts with `_` (e.g. "work/_vpn_probe") or the file has a `-- private: true`
+/// header. Private gatekeepers can be required and evaluated explicitly, but
+/// aren't synced, listed, or written to generated caches.
+pub fn is_private_gatekeeper(name: &str) -> bool {
+    let name = base_name(name);
+    if name.split('/').any(|component| component.starts_with('_')) {
+        return true;
+    }
+    get_gatekeeper_path(name)
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .is_some_and(|script| has_private_header(&script))
+}
+
+/// Parse a header comment like: -- private: true
+fn has_private_header(script: &str) -> bool {
+    script.lines().any(|line| {
+        line.trim()
+            .strip_prefix("--")
+            .and_then(|rest| rest.trim().strip_prefix("private:"))
+            .is_some_and(|value| value.trim() == "true")
+    })
+}
+
 pub fn find_all_gatekeepers() -> Result<Vec<String>> {
     let mut config_dir = get_config_dir()?;
     config_dir.push("gatekeepers");
@@ -121,6 +283,7 @@ pub fn find_all_gatekeepers() -> Result<Vec<String>> {
 
     let mut gatekeepers = Vec::new();
     find_gatekeepers_recursive(&config_dir, "", &mut gatekeepers)?;
+    gatekeepers.retain(|name| !is_private_gatekeeper(name));
     Ok(gatekeepers)
 }
 
@@ -133,46 +296,45 @@ fn find_gatekeepers_recursive(
         let entry = entry?;
         let path = entry.path();
 
-        if path.is_file() && path.extension().map_or(false, |ext| ext == "lua") {
-            if let Some(stem) = path.file_stem() {
-                if let Some(name) = stem.to_str() {
-                    // Skip init.lua files - they're accessed as directory aggregates
-                    if name == "init" {
-                        continue;
-                    }
-
-                    let full_name = if prefix.is_empty() {
-                        name.to_string()
-                    } else {
-                        format!("{}/{}", prefix, name)
-                    };
-                    gatekeepers.push(full_name);
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "lua") {
+            if let Some(stem) = path.file_stem()
+                && let Some(name) = stem.to_str()
+            {
+                // Skip init.lua files - they're accessed as directory aggregates
+                if name == "init" {
+                    continue;
                 }
-            }
-        } else if path.is_dir() {
-            if let Some(dir_name) = path.file_name() {
-                if let Some(dir_str) = dir_name.to_str() {
-                    // Check if this directory has an init.lua
-                    let init_path = path.join("init.lua");
-                    if init_path.exists() {
-                        // Add the directory itself as a gatekeeper
-                        let dir_gk_name = if prefix.is_empty() {
-                            dir_str.to_string()
-                        } else {
-                            format!("{}/{}", prefix, dir_str)
-                        };
-                        gatekeepers.push(dir_gk_name);
-                    }
 
-                    // Recurse into subdirectory
-                    let new_prefix = if prefix.is_empty() {
-                        dir_str.to_string()
-                    } else {
-                        format!("{}/{}", prefix, dir_str)
-                    };
-                    find_gatekeepers_recursive(&path, &new_prefix, gatekeepers)?;
-                }
+                let full_name = if prefix.is_empty() {
+                    name.to_string()
+                } else {
+                    format!("{}/{}", prefix, name)
+                };
+                gatekeepers.push(full_name);
             }
+        } else if path.is_dir()
+            && let Some(dir_name) = path.file_name()
+            && let Some(dir_str) = dir_name.to_str()
+        {
+            // Check if this directory has an init.lua
+            let init_path = path.join("init.lua");
+            if init_path.exists() {
+                // Add the directory itself as a gatekeeper
+                let dir_gk_name = if prefix.is_empty() {
+                    dir_str.to_string()
+                } else {
+                    format!("{}/{}", prefix, dir_str)
+                };
+                gatekeepers.push(dir_gk_name);
+            }
+
+            // Recurse into subdirectory
+            let new_prefix = if prefix.is_empty() {
+                dir_str.to_string()
+            } else {
+                format!("{}/{}", prefix, dir_str)
+            };
+            find_gatekeepers_recursive(&path, &new_prefix, gatekeepers)?;
         }
     }
     Ok(())
@@ -257,6 +419,12 @@ mod tests {
         test_helper("myapp/composite", true)
     }
 
+    // dir() keeps multivariate values
+    #[test]
+    fn test_dir_returns_values() -> Result<()> {
+        test_helper("dir_values", true)
+    }
+
     // Test basic evaluator types
     #[test]
     fn test_bool_pass() -> Result<()> {
@@ -312,6 +480,120 @@ mod tests {
         test_helper("os_unix_pass", expected)
     }
 
+    #[test]
+    fn test_string_value_gatekeeper() -> Result<()> {
+        let expected = if cfg!(target_os = "macos") { "light" } else { "dark" };
+        let result = load_and_evaluate_gatekeeper("theme")?;
+        assert_eq!(result.value, GatekeeperValue::String(expected.to_string()));
+
+        // Required gatekeepers hand their typed value to the caller
+        test_helper("value_require_pass", true)
+    }
+
+    #[test]
+    fn test_lib_module_pass() -> Result<()> {
+        test_helper("lib_pass", true)
+    }
+
+    #[test]
+    fn test_dependencies_are_recorded() -> Result<()> {
+        let result = load_and_evaluate_gatekeeper("lib_pass")?;
+        assert_eq!(result.dependencies, vec![get_lib_path("corp/hosts")?.unwrap()]);
+
+        // Dependencies of required gatekeepers are included transitively
+        let result = load_and_evaluate_gatekeeper("myapp/composite")?;
+        assert!(result.dependencies.contains(&get_gatekeeper_path("myapp/devserver")?));
+        assert!(result.dependencies.contains(&get_gatekeeper_path("myapp/laptop")?));
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_lib_path() -> Result<()> {
+        assert!(get_lib_path("corp/hosts")?.is_some_and(|path| path.ends_with("lib/corp/hosts.lua")));
+        assert!(get_lib_path("corp/missing")?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_variables() -> Result<()> {
+        let vars = Variables::from([("dir".to_string(), "/home/me/work/api".to_string())]);
+        let result = load_and_evaluate_gatekeeper_with_previous("is_work_dir", None, &vars)?;
+        assert_eq!(result.value, true);
+
+        let vars = Variables::from([("dir".to_string(), "/home/me/src".to_string())]);
+        let result = load_and_evaluate_gatekeeper_with_previous("is_work_dir", None, &vars)?;
+        assert_eq!(result.value, false);
+
+        // Required gatekeepers see the caller's variables
+        let vars = Variables::from([("dir".to_string(), "/srv/work/app".to_string())]);
+        let result = load_and_evaluate_gatekeeper_with_previous("args_require", None, &vars)?;
+        assert_eq!(result.value, true);
+
+        // args is empty, not nil, without variables
+        test_helper("is_work_dir", false)
+    }
+
+    #[test]
+    fn test_cache_key() {
+        assert_eq!(cache_key("project/is_work", &Variables::new()), "project/is_work");
+
+        // Variables are sorted by name
+        let vars = Variables::from([
+            ("profile".to_string(), "ci".to_string()),
+            ("dir".to_string(), "/a".to_string()),
+        ]);
+        let key = cache_key("project/is_work", &vars);
+        assert_eq!(key, "project/is_work{dir=/a,profile=ci}");
+        assert_eq!(base_name(&key), "project/is_work");
+        assert_eq!(base_name("project/is_work"), "project/is_work");
+
+        assert_eq!(parse_cache_key(&key), Some(("project/is_work", vars)));
+        assert_eq!(
+            parse_cache_key("project/is_work"),
+            Some(("project/is_work", Variables::new()))
+        );
+        assert_eq!(parse_cache_key("project/is_work{dir}"), None);
+
+        // A value containing the separators doesn't collide with separate variables
+        let joined = Variables::from([("a".to_string(), "1,b=2".to_string())]);
+        let separate = Variables::from([
+            ("a".to_string(), "1".to_string()),
+            ("b".to_string(), "2".to_string()),
+        ]);
+        let joined_key = cache_key("p", &joined);
+        assert_eq!(joined_key, "p{a=1%2Cb%3D2}");
+        assert_ne!(joined_key, cache_key("p", &separate));
+        assert_eq!(parse_cache_key(&joined_key), Some(("p", joined)));
+
+        // Variables don't make a gatekeeper private
+        let vars = Variables::from([("dir".to_string(), "/a/_build".to_string())]);
+        assert!(!is_private_gatekeeper(&cache_key("bool_pass", &vars)));
+    }
+
+    #[test]
+    fn test_private_gatekeepers() -> Result<()> {
+        assert!(is_private_gatekeeper("myapp/_probe"));
+        assert!(is_private_gatekeeper("_internal/helper"));
+        assert!(is_private_gatekeeper("private_header"));
+        assert!(!is_private_gatekeeper("bool_pass"));
+        assert!(!is_private_gatekeeper("my_app/probe"));
+
+        // Excluded from sync, but still evaluable directly and via require
+        let gatekeepers = find_all_gatekeepers()?;
+        assert!(!gatekeepers.contains(&"myapp/_probe".to_string()));
+        assert!(!gatekeepers.contains(&"private_header".to_string()));
+        test_helper("myapp/_probe", true)?;
+        test_helper("private_pass", true)
+    }
+
+    #[test]
+    fn test_has_private_header() {
+        assert!(has_private_header("-- private: true\nreturn true"));
+        assert!(has_private_header("-- ttl: 60\n--private:true\nreturn true"));
+        assert!(!has_private_header("-- private: false\nreturn true"));
+        assert!(!has_private_header("return true"));
+    }
+
     // Test find_all_gatekeepers includes subdirectory gatekeepers
     #[test]
     fn test_find_all_gatekeepers_includes_subdirectories() -> Result<()> {
@@ -323,6 +605,9 @@ mod tests {
         assert!(gatekeepers.contains(&"os/linux".to_string()));
         assert!(gatekeepers.contains(&"myapp/composite".to_string()));
 
+        // Shared modules in lib/ are never gatekeepers
+        assert!(!gatekeepers.iter().any(|name| name.contains("corp")));
+
         // Verify we have some subdirectory gatekeepers
         let subdir_gatekeepers: Vec<_> = gatekeepers
             .iter()
@@ -0,0 +1,249 @@
+use anyhow::Result;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::value::GatekeeperValue;
+
+/// A new value seen by recent evaluations that hasn't replaced the cached one yet
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Pending {
+    pub value: GatekeeperValue,
+    /// Consecutive evaluations that returned it
+    pub count: u32,
+}
+
+/// A gatekeeper's cached state from before the current evaluation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Previous {
+    pub value: GatekeeperValue,
+    /// When it was last evaluated
+    pub ts: u64,
+    /// When its value last changed
+    pub changed_at: u64,
+    pub pending: Option<Pending>,
+}
+
+/// Options from `-- debounce: 3` and `-- hold_true: 10m` headers
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Hysteresis {
+    /// Evaluations in a row that must agree before the value changes
+    pub debounce: Option<u32>,
+    /// Seconds a true value is kept after it turned true
+    pub hold_true: Option<u64>,
+}
+
+/// A freshly evaluated value after debounce and hold were applied
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stabilized {
+    pub value: GatekeeperValue,
+    pub pending: Option<Pending>,
+    /// When the value last changed, tracked while `hold_true` is in use
+    pub changed_at: Option<u64>,
+    /// Seconds until a hold on a true value runs out
+    pub hold_remaining: Option<u64>,
+}
+
+impl Hysteresis {
+    /// Read `-- debounce:` and `-- hold_true:` headers from a script
+    pub fn parse(script: &str) -> Result<Self> {
+        let mut hysteresis = Self::default();
+        for line in script.lines() {
+            let Some(header) = line.trim().strip_prefix("--") else {
+                continue;
+            };
+            let header = header.trim();
+            if let Some(count) = header.strip_prefix("debounce:") {
+                let count = count.trim();
+                hysteresis.debounce = Some(count.parse().map_err(|_| {
+                    anyhow::anyhow!("Invalid debounce '{}', expected a number of evaluations", count)
+                })?);
+            } else if let Some(duration) = header.strip_prefix("hold_true:") {
+                hysteresis.hold_true = Some(parse_duration(duration.trim())?);
+            }
+        }
+        Ok(hysteresis)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.debounce.is_none() && self.hold_true.is_none()
+    }
+
+    /// Decide which value to cache, given what was just evaluated and what was cached before
+    pub fn apply(&self, value: GatekeeperValue, previous: Option<&Previous>, now: u64) -> Stabilized {
+        let Some(previous) = previous else {
+            return Stabilized {
+                value,
+                pending: None,
+                changed_at: self.hold_true.map(|_| now),
+                hold_remaining: None,
+            };
+        };
+
+        let mut value = value;
+        let mut pending = None;
+
+        // Only change after `debounce` evaluations in a row agree on the new value
+        if let Some(required) = self.debounce
+            && value != previous.value
+        {
+            let count = match &previous.pending {
+                Some(seen) if seen.value == value => seen.count + 1,
+                _ => 1,
+            };
+            if count < required {
+                pending = Some(Pending { value, count });
+                value = previous.value.clone();
+            }
+        }
+
+        // Keep a true value until it has been true for `hold_true` seconds
+        let mut hold_remaining = None;
+        if let Some(hold) = self.hold_true
+            && previous.value == true
+            && value != true
+        {
+            let until = previous.changed_at + hold;
+            if now < until {
+                value = true.into();
+                hold_remaining = Some(until - now);
+            }
+        }
+
+        let changed_at = if value == previous.value {
+            previous.changed_at
+        } else {
+            now
+        };
+
+        Stabilized {
+            value,
+            pending,
+            changed_at: self.hold_true.map(|_| changed_at),
+            hold_remaining,
+        }
+    }
+}
+
+/// Parse a duration like "90", "30s", "10m", "2h" or "1d" into seconds
+pub fn parse_duration(text: &str) -> Result<u64> {
+    let (number, unit) = match text.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => text.split_at(index),
+        None => (text, "s"),
+    };
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => 0,
+    };
+    match number.parse::<u64>() {
+        Ok(number) if multiplier > 0 => Ok(number * multiplier),
+        _ => anyhow::bail!(
+            "Invalid duration '{}', use seconds or a number followed by s, m, h or d",
+            text
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn previous(value: impl Into<GatekeeperValue>, changed_at: u64, pending: Option<Pending>) -> Previous {
+        Previous {
+            value: value.into(),
+            ts: changed_at,
+            changed_at,
+            pending,
+        }
+    }
+
+    #[test]
+    fn test_parse_headers() -> Result<()> {
+        let hysteresis = Hysteresis::parse("-- debounce: 3\n-- hold_true: 10m\nreturn true")?;
+        assert_eq!(hysteresis.debounce, Some(3));
+        assert_eq!(hysteresis.hold_true, Some(600));
+
+        assert!(Hysteresis::parse("return true")?.is_empty());
+        assert!(Hysteresis::parse("-- debounce: often").is_err());
+        assert!(Hysteresis::parse("-- hold_true: 10x").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_duration() -> Result<()> {
+        assert_eq!(parse_duration("90")?, 90);
+        assert_eq!(parse_duration("30s")?, 30);
+        assert_eq!(parse_duration("10m")?, 600);
+        assert_eq!(parse_duration("2h")?, 7200);
+        assert_eq!(parse_duration("1d")?, 86400);
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("m").is_err());
+        assert!(parse_duration("1.5h").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_first_evaluation_is_taken_as_is() {
+        let hysteresis = Hysteresis {
+            debounce: Some(3),
+            hold_true: Some(600),
+        };
+        let stabilized = hysteresis.apply(false.into(), None, 1000);
+        assert_eq!(stabilized.value, false);
+        assert_eq!(stabilized.pending, None);
+        assert_eq!(stabilized.changed_at, Some(1000));
+    }
+
+    #[test]
+    fn test_debounce() {
+        let hysteresis = Hysteresis {
+            debounce: Some(3),
+            hold_true: None,
+        };
+
+        // First two disagreeing evaluations are held back
+        let first = hysteresis.apply(false.into(), Some(&previous(true, 0, None)), 100);
+        assert_eq!(first.value, true);
+        assert_eq!(first.pending, Some(Pending { value: false.into(), count: 1 }));
+
+        let second = hysteresis.apply(false.into(), Some(&previous(true, 0, first.pending)), 200);
+        assert_eq!(second.value, true);
+        assert_eq!(second.pending.as_ref().map(|pending| pending.count), Some(2));
+
+        // The third one changes the value
+        let third = hysteresis.apply(false.into(), Some(&previous(true, 0, second.pending.clone())), 300);
+        assert_eq!(third.value, false);
+        assert_eq!(third.pending, None);
+
+        // Agreeing with the cached value resets the count
+        let reset = hysteresis.apply(true.into(), Some(&previous(true, 0, second.pending)), 300);
+        assert_eq!(reset.value, true);
+        assert_eq!(reset.pending, None);
+    }
+
+    #[test]
+    fn test_hold_true() {
+        let hysteresis = Hysteresis {
+            debounce: None,
+            hold_true: Some(600),
+        };
+
+        // True since t=1000, so a false at t=1200 is held for another 400s
+        let held = hysteresis.apply(false.into(), Some(&previous(true, 1000, None)), 1200);
+        assert_eq!(held.value, true);
+        assert_eq!(held.changed_at, Some(1000));
+        assert_eq!(held.hold_remaining, Some(400));
+
+        let released = hysteresis.apply(false.into(), Some(&previous(true, 1000, None)), 1600);
+        assert_eq!(released.value, false);
+        assert_eq!(released.changed_at, Some(1600));
+        assert_eq!(released.hold_remaining, None);
+
+        // Turning true is never delayed
+        let on = hysteresis.apply(true.into(), Some(&previous(false, 1000, None)), 1100);
+        assert_eq!(on.value, true);
+        assert_eq!(on.changed_at, Some(1100));
+    }
+}
@@ -1,4 +1,14 @@
+// The CLI commands in cache and settings are only used by the binary
+#[allow(dead_code)]
+mod cache;
 pub mod lua_executor;
+#[allow(dead_code)]
+mod settings;
+mod diagnostic;
+mod facts;
 mod gatekeeper;
+mod hysteresis;
+mod value;
 
 pub use gatekeeper::{GatekeeperResult, load_and_evaluate_gatekeeper, load_and_evaluate_gatekeeper_with_context};
+pub use value::GatekeeperValue;
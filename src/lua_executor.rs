@@ -1,14 +1,89 @@
 use anyhow::Result;
+use chrono::Datelike;
 use mlua::prelude::*;
 use regex::Regex;
 use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::collections::HashSet;
 use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::debug;
+
+use crate::diagnostic::Diagnostic;
+use crate::facts;
+use crate::facts::environment::process_env;
+use crate::hysteresis::Hysteresis;
+use crate::hysteresis::Pending;
+use crate::hysteresis::Previous;
+use crate::value::GatekeeperValue;
+pub use crate::facts::time::Clock;
+
+/// Tracing target for `log.*()` calls in gatekeeper scripts
+pub const SCRIPT_LOG_TARGET: &str = "dotgk::script";
+
+/// Convert an error from a Rust helper into a Lua runtime error
+fn runtime_error(e: impl std::fmt::Display) -> LuaError {
+    LuaError::RuntimeError(e.to_string())
+}
+
+/// A version exposed to Lua; compares against other versions and version strings
+#[derive(Clone)]
+struct LuaVersion(facts::version::Version);
+
+impl LuaVersion {
+    /// Accept a version, a version string or a number on either side of a comparison
+    fn coerce(value: &LuaValue) -> LuaResult<facts::version::Version> {
+        match value {
+            LuaValue::UserData(data) => Ok(data.borrow::<LuaVersion>()?.0.clone()),
+            LuaValue::String(s) => facts::version::Version::parse(s.to_str()?).map_err(runtime_error),
+            LuaValue::Integer(n) => facts::version::Version::parse(&n.to_string()).map_err(runtime_error),
+            LuaValue::Number(n) => facts::version::Version::parse(&n.to_string()).map_err(runtime_error),
+            other => Err(LuaError::RuntimeError(format!(
+                "Cannot compare a version with a {}",
+                other.type_name()
+            ))),
+        }
+    }
+}
+
+impl LuaUserData for LuaVersion {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_field_method_get("major", |_, this| Ok(this.0.component(0)));
+        fields.add_field_method_get("minor", |_, this| Ok(this.0.component(1)));
+        fields.add_field_method_get("patch", |_, this| Ok(this.0.component(2)));
+        fields.add_field_method_get("pre", |_, this| Ok(this.0.pre.clone()));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        // __eq only fires when both sides are versions; use version(a) == version(b)
+        methods.add_meta_function(LuaMetaMethod::Eq, |_, (a, b): (LuaValue, LuaValue)| {
+            Ok(Self::coerce(&a)? == Self::coerce(&b)?)
+        });
+        methods.add_meta_function(LuaMetaMethod::Lt, |_, (a, b): (LuaValue, LuaValue)| {
+            Ok(Self::coerce(&a)? < Self::coerce(&b)?)
+        });
+        methods.add_meta_function(LuaMetaMethod::Le, |_, (a, b): (LuaValue, LuaValue)| {
+            Ok(Self::coerce(&a)? <= Self::coerce(&b)?)
+        });
+        methods.add_meta_method(LuaMetaMethod::ToString, |_, this, ()| Ok(this.0.to_string()));
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct LuaGatekeeperResult {
-    pub value: bool,
+    pub value: GatekeeperValue,
     pub ttl: Option<u64>,
+    /// Files loaded through `require` (gatekeepers and lib modules, transitively)
+    pub dependencies: Vec<PathBuf>,
+    /// Why the script returned its value, from a `reason` field or `because()`
+    pub reason: Option<String>,
+    /// Cache entries read with `cached()`/`is_set()` and the values seen (`None` if unset)
+    pub cache_dependencies: BTreeMap<String, Option<GatekeeperValue>>,
+    /// A new value held back by `-- debounce:`, and how many evaluations returned it
+    pub pending: Option<Pending>,
+    /// When the value last changed, tracked for `-- hold_true:`
+    pub changed_at: Option<u64>,
 }
 
 /// Tracks visited gatekeepers to detect circular dependencies
@@ -38,6 +113,40 @@ impl EvaluationContext {
     }
 }
 
+/// Earliest instant (unix seconds) at which a check evaluated so far could change its answer
+#[derive(Debug, Default)]
+struct NextBoundary(Option<i64>);
+
+/// Reason given by the last `because()` call in the current evaluation
+#[derive(Default)]
+struct Reason(Option<String>);
+
+/// Cache entries read during the current evaluation, with the values seen
+#[derive(Default)]
+struct CacheReads(BTreeMap<String, Option<GatekeeperValue>>);
+
+/// The gatekeeper's cached state before this evaluation, for `previous()` and hysteresis
+struct PreviousEntry(Previous);
+
+/// The gatekeeper being run and the file it was loaded from, for chunk names and diagnostics
+struct Source {
+    name: String,
+    path: PathBuf,
+}
+
+/// Variables from `--var`, handed on to required gatekeepers
+struct Args(BTreeMap<String, String>);
+
+/// Cache file read by `cached()`/`is_set()`, overriding the default location
+struct CachePath(PathBuf);
+
+/// Files loaded through `require` during the current evaluation
+#[derive(Default)]
+struct Dependencies(Vec<PathBuf>);
+
+/// Directory of the gatekeeper being evaluated; relative paths resolve against it
+struct ScriptDir(PathBuf);
+
 pub struct LuaExecutor {
     lua: Lua,
     _context: std::rc::Rc<EvaluationContext>,
@@ -45,8 +154,18 @@ pub struct LuaExecutor {
 
 impl LuaExecutor {
     pub fn new() -> Result<Self> {
+        Self::with_clock(Clock::from_env())
+    }
+
+    /// Create an executor whose time-based checks read from `clock`
+    pub fn with_clock(clock: Clock) -> Result<Self> {
         let lua = Lua::new();
         let context = std::rc::Rc::new(EvaluationContext::default());
+        lua.set_app_data(clock);
+        lua.set_app_data(NextBoundary::default());
+        lua.set_app_data(Dependencies::default());
+        lua.set_app_data(Reason::default());
+        lua.set_app_data(CacheReads::default());
 
         // Register DSL functions
         Self::register_functions(&lua, context.clone())?;
@@ -61,26 +180,93 @@ impl LuaExecutor {
         Ok(())
     }
 
+    /// Resolve relative paths in path-taking functions against `dir` instead of the cwd
+    pub fn set_script_dir(&self, dir: &Path) {
+        self.lua.set_app_data(ScriptDir(dir.to_path_buf()));
+    }
+
+    /// Name the script after its gatekeeper and file, so errors and tracebacks point at it
+    pub fn set_source(&self, name: &str, path: &Path) {
+        self.lua.set_app_data(Source {
+            name: name.to_string(),
+            path: path.to_path_buf(),
+        });
+    }
+
+    /// Expose `--var key=value` variables to the script, and the gatekeepers it
+    /// requires, as the `args` table
+    pub fn set_args(&self, vars: &BTreeMap<String, String>) -> Result<()> {
+        self.lua.set_app_data(Args(vars.clone()));
+        let args = self
+            .lua
+            .create_table_from(vars.iter().map(|(key, value)| (key.as_str(), value.as_str())))
+            .map_err(|e| anyhow::anyhow!("Failed to create args table: {}", e))?;
+        self.lua
+            .globals()
+            .set("args", args)
+            .map_err(|e| anyhow::anyhow!("Failed to set args: {}", e))?;
+        Ok(())
+    }
+
+    /// Give the script its last cached state, for `previous()` and debounce/hold headers
+    pub fn set_previous(&self, previous: Previous) {
+        self.lua.set_app_data(PreviousEntry(previous));
+    }
+
+    /// Read other entries for `cached()`/`is_set()` from `path` instead of the default cache
+    #[cfg(test)]
+    pub fn set_cache_path(&self, path: &Path) {
+        self.lua.set_app_data(CachePath(path.to_path_buf()));
+    }
+
     fn register_functions(lua: &Lua, context: std::rc::Rc<EvaluationContext>) -> Result<()> {
         let globals = lua.globals();
 
-        // file_exists(path: string) -> bool
-        let file_exists = lua.create_function(|_, path: String| {
-            Ok(Path::new(&path).exists())
-        })?;
-        globals.set("file_exists", file_exists)?;
+        // Register file, directory and glob checks
+        Self::register_file_functions(lua)?;
 
-        // hostname(target: string) -> bool
-        let hostname_check = lua.create_function(|_, target: String| {
-            let current = hostname::get()
-                .map_err(|e| LuaError::RuntimeError(format!("Failed to get hostname: {}", e)))?;
-            let current_str = current
-                .to_str()
-                .ok_or_else(|| LuaError::RuntimeError("Invalid hostname encoding".into()))?;
-            Ok(current_str == target)
+        // hostname(target: string | table) -> bool
+        // Targets may be glob patterns ("devvm*.corp") and match the hostname or FQDN
+        let hostname_check = lua.create_function(|_, target: LuaValue| {
+            let patterns = Self::string_list(target, "hostname")?;
+            let candidates = Self::hostname_candidates()?;
+            for pattern in patterns {
+                if facts::host::matches_glob(&pattern, &candidates).map_err(runtime_error)? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
         })?;
         globals.set("hostname", hostname_check)?;
 
+        // hostname_match(pattern: string) -> bool
+        // Regex match against the hostname or FQDN
+        let hostname_match = lua.create_function(|_, pattern: String| {
+            let re = Regex::new(&pattern).map_err(|e| {
+                LuaError::RuntimeError(format!("Invalid hostname regex '{}': {}", pattern, e))
+            })?;
+            let candidates = Self::hostname_candidates()?;
+            Ok(candidates.iter().any(|candidate| re.is_match(candidate)))
+        })?;
+        globals.set("hostname_match", hostname_match)?;
+
+        // short_hostname() -> string
+        let short_hostname = lua.create_function(|_, ()| {
+            let hostname = facts::host::hostname().map_err(runtime_error)?;
+            Ok(facts::host::short_hostname(&hostname).to_string())
+        })?;
+        globals.set("short_hostname", short_hostname)?;
+
+        // fqdn() -> string
+        let fqdn = lua.create_function(|_, ()| {
+            let hostname = facts::host::hostname().map_err(runtime_error)?;
+            Ok(facts::host::fqdn(&facts::root(), &hostname))
+        })?;
+        globals.set("fqdn", fqdn)?;
+
+        // Register user, group and privilege checks
+        Self::register_user_functions(lua)?;
+
         // os(name: string) -> bool
         let os_check = lua.create_function(|_, name: String| {
             let matches = match name.as_str() {
@@ -99,29 +285,69 @@ impl LuaExecutor {
         })?;
         globals.set("os", os_check)?;
 
+        // Register os-release based distribution checks
+        Self::register_distro_functions(lua)?;
+
+        // Register CPU architecture and hardware checks
+        Self::register_hardware_functions(lua)?;
+
+        // Register container, WSL, VM and session checks
+        Self::register_environment_functions(lua)?;
+
+        // Register interface, address, route and port checks
+        Self::register_network_functions(lua)?;
+
+        // Register process and mount checks
+        Self::register_system_functions(lua)?;
+
+        // Register git repository checks
+        Self::register_git_functions(lua)?;
+
+        // Register weekday, time, date and timezone checks
+        Self::register_time_functions(lua)?;
+
+        // Register machine identity and percentage rollouts
+        Self::register_rollout_functions(lua)?;
+
+        // Register JSON/TOML/YAML/INI value lookups
+        Self::register_data_functions(lua)?;
+
+        // Register version parsing and tool version probes
+        Self::register_version_functions(lua)?;
+
+        // Register reads of other cached entries
+        Self::register_cache_functions(lua)?;
+
         // Register custom require searcher for loading other gatekeepers
         Self::register_require_searcher(lua, context.clone())?;
 
-        // any(checks: table) -> bool
-        let any_check = lua.create_function(|_, checks: Vec<bool>| Ok(checks.iter().any(|&x| x)))?;
-        globals.set("any", any_check)?;
+        // Register any/all/none combinators
+        Self::register_combinator_functions(lua)?;
 
-        // all(checks: table) -> bool
-        let all_check = lua.create_function(|_, checks: Vec<bool>| Ok(checks.iter().all(|&x| x)))?;
-        globals.set("all", all_check)?;
+        // Register log.* and a print that can't corrupt the printed value
+        Self::register_log_functions(lua)?;
 
-        // none(checks: table) -> bool
-        let none_check =
-            lua.create_function(|_, checks: Vec<bool>| Ok(!checks.iter().any(|&x| x)))?;
-        globals.set("none", none_check)?;
+        // because(reason: string) -> false
+        // Records why the gatekeeper returned its value; returns false so it can
+        // end a chain: `return on_vpn() or because("not on corp VPN")`
+        let because = lua.create_function(|lua, reason: String| {
+            if let Some(mut current) = lua.app_data_mut::<Reason>() {
+                current.0 = Some(reason);
+            }
+            Ok(false)
+        })?;
+        globals.set("because", because)?;
 
         // bool(value: bool) -> bool (identity function for clarity)
         let bool_check = lua.create_function(|_, value: bool| Ok(value))?;
         globals.set("bool", bool_check)?;
 
-        // dir(path: optional string) -> table of booleans
+        // args: table of `--var key=value` variables, empty unless set with set_args()
+        globals.set("args", lua.create_table()?)?;
+
+        // dir(path: optional string) -> table of values
         // Scans a directory and loads all gatekeeper files, returning their results
-        let dir_func = lua.create_function(|lua_ctx, path: Option<String>| -> LuaResult<Vec<bool>> {
+        let dir_func = lua.create_function(|lua_ctx, path: Option<String>| -> LuaResult<Vec<LuaValue>> {
             // Determine directory to scan
             let dir_path_str = path.unwrap_or_else(|| {
                 // Get _DOTGK_CURRENT_DIR global if set (for init.lua context)
@@ -150,27 +376,32 @@ impl LuaExecutor {
                 let entry = entry.map_err(|e| LuaError::RuntimeError(e.to_string()))?;
                 let file_path = entry.path();
 
-                if file_path.is_file() && file_path.extension().map_or(false, |ext| ext == "lua") {
-                    if let Some(stem) = file_path.file_stem() {
-                        let stem_str = stem.to_string_lossy();
-                        // Skip init.lua to avoid recursion
-                        if stem_str != "init" {
-                            // Build module name: "meta" + "devserver" -> "meta.devserver"
-                            let module_name = if dir_path_str == "." {
-                                stem_str.to_string()
-                            } else {
-                                format!("{}.{}", dir_path_str.replace('/', "."), stem_str)
-                            };
-
-                            // Load via require (uses cache)
-                            let result: bool = lua_ctx
-                                .load(&format!("return require('{}')", module_name))
-                                .eval()
-                                .map_err(|e| LuaError::RuntimeError(format!(
+                if file_path.is_file() && file_path.extension().is_some_and(|ext| ext == "lua")
+                    && let Some(stem) = file_path.file_stem()
+                {
+                    let stem_str = stem.to_string_lossy();
+                    // Skip init.lua to avoid recursion
+                    if stem_str != "init" {
+                        // Build module name: "meta" + "devserver" -> "meta.devserver"
+                        let module_name = if dir_path_str == "." {
+                            stem_str.to_string()
+                        } else {
+                            format!("{}.{}", dir_path_str.replace('/', "."), stem_str)
+                        };
+
+                        // Load via require (uses cache)
+                        let result: LuaValue = lua_ctx
+                            .load(format!("return require('{}')", module_name))
+                            .set_name("=dir")
+                            .eval()
+                            .map_err(|e| match Diagnostic::find(&e) {
+                                // Keep the failing gatekeeper's own location
+                                Some(diagnostic) => LuaError::external(diagnostic.clone()),
+                                None => LuaError::RuntimeError(format!(
                                     "Failed to load '{}': {}", module_name, e
-                                )))?;
-                            results.push(result);
-                        }
+                                )),
+                            })?;
+                        results.push(result);
                     }
                 }
             }
@@ -182,199 +413,1729 @@ impl LuaExecutor {
         Ok(())
     }
 
-    fn register_require_searcher(lua: &Lua, context: std::rc::Rc<EvaluationContext>) -> Result<()> {
-        // Get package.searchers table
-        let package: LuaTable = lua.globals().get("package")
-            .map_err(|e| anyhow::anyhow!("Failed to get package table: {}", e))?;
-        let searchers: LuaTable = package.get("searchers")
-            .map_err(|e| anyhow::anyhow!("Failed to get package.searchers: {}", e))?;
+    fn register_file_functions(lua: &Lua) -> Result<()> {
+        let globals = lua.globals();
 
-        // Create custom searcher function
-        let custom_searcher = lua.create_function(move |lua_ctx, module_name: String| {
-            // Convert "meta.devserver" -> "meta/devserver"
-            let gk_name = module_name.replace('.', "/");
+        // Each check takes a path with `~` and `$VAR` expanded; relative paths
+        // resolve against the gatekeeper's directory
+        type Check = fn(&Path) -> bool;
+        let checks: [(&str, Check); 5] = [
+            // file_exists(path: string) -> bool
+            ("file_exists", |path| path.exists()),
+            // is_dir(path: string) -> bool
+            ("is_dir", |path| path.is_dir()),
+            // is_file(path: string) -> bool
+            ("is_file", |path| path.is_file()),
+            // is_symlink(path: string) -> bool
+            ("is_symlink", facts::paths::is_symlink),
+            // is_executable(path: string) -> bool
+            ("is_executable", facts::paths::is_executable),
+        ];
+        for (name, check) in checks {
+            let function = lua.create_function(move |lua, path: String| {
+                Ok(Self::resolve_path(lua, &path).is_some_and(|path| check(&path)))
+            })?;
+            globals.set(name, function)?;
+        }
 
-            // Determine paths to try:
-            // For "meta" -> try ["meta", "meta/init"]
-            // For "meta/devserver" -> try ["meta/devserver"]
-            let paths_to_try: Vec<String> = if !gk_name.contains('/') {
-                // Simple name like "meta" - try both direct and init.lua
-                vec![gk_name.clone(), format!("{}/init", gk_name)]
-            } else {
-                // Nested name like "meta/devserver" - just try as-is
-                vec![gk_name.clone()]
+        // glob_exists(pattern: string) -> bool
+        let glob_exists = lua.create_function(|lua, pattern: String| {
+            let Some(expanded) = facts::paths::expand(&pattern, None) else {
+                return Ok(false);
+            };
+            // Escape the base so its own metacharacters aren't treated as a pattern
+            let pattern = match Self::script_dir(lua) {
+                Some(dir) if expanded.is_relative() => format!(
+                    "{}/{}",
+                    glob::Pattern::escape(&dir.to_string_lossy()),
+                    expanded.to_string_lossy()
+                ),
+                _ => expanded.to_string_lossy().into_owned(),
             };
+            facts::paths::glob_exists(&pattern).map_err(runtime_error)
+        })?;
+        globals.set("glob_exists", glob_exists)?;
 
-            // Try each path
-            for path in &paths_to_try {
-                // Check if gatekeeper file exists
-                if let Ok(gk_path) = crate::gatekeeper::get_gatekeeper_path(path) {
-                    if gk_path.exists() {
-                        // Found it! Check for circular dependency
-                        context
-                            .visit(path)
-                            .map_err(|e| LuaError::RuntimeError(e.to_string()))?;
-
-                        // Create loader function that will be called by require()
-                        let path_clone = path.clone();
-                        let context_clone = context.clone();
-
-                        let loader = lua_ctx.create_function(move |_lua, _: ()| {
-                            // Load and evaluate the gatekeeper
-                            // load_and_evaluate_gatekeeper auto-detects init.lua and sets context
-                            let result = match crate::gatekeeper::load_and_evaluate_gatekeeper(&path_clone) {
-                                Ok(result) => {
-                                    context_clone.leave(&path_clone);
-                                    Ok(result.value)
-                                }
-                                Err(e) => {
-                                    context_clone.leave(&path_clone);
-                                    Err(LuaError::RuntimeError(format!(
-                                        "Failed to load gatekeeper '{}': {}\nHint: Check that the gatekeeper exists and has valid syntax",
-                                        path_clone, e
-                                    )))
-                                }
-                            };
+        // file_newer_than(path: string, seconds: number) -> bool
+        // True if the file was modified less than `seconds` ago; false if it is missing
+        let file_newer_than = lua.create_function(|lua, (path, seconds): (String, u64)| {
+            Ok(Self::resolve_path(lua, &path)
+                .is_some_and(|path| facts::paths::modified_within(&path, seconds)))
+        })?;
+        globals.set("file_newer_than", file_newer_than)?;
+
+        Ok(())
+    }
 
-                            result
-                        })?;
+    fn register_combinator_functions(lua: &Lua) -> Result<()> {
+        let globals = lua.globals();
 
-                        // Return the loader function
-                        return Ok(loader);
-                    }
+        // Checks may be booleans or functions returning booleans; functions are
+        // called in order and only until the answer is known
+
+        // any(checks: table) -> bool
+        let any_check = lua.create_function(|_, checks: LuaTable| {
+            for (index, check) in checks.sequence_values::<LuaValue>().enumerate() {
+                if Self::check_result("any", index + 1, check?)? {
+                    return Ok(true);
                 }
             }
+            Ok(false)
+        })?;
+        globals.set("any", any_check)?;
 
-            // Not found - return error message with paths tried
-            Err(LuaError::RuntimeError(format!(
-                "Gatekeeper '{}' not found (tried: {})",
-                module_name,
-                paths_to_try.join(".lua, ") + ".lua"
-            )))
-        })
-        .map_err(|e| anyhow::anyhow!("Failed to create custom searcher: {}", e))?;
+        // all(checks: table) -> bool
+        let all_check = lua.create_function(|_, checks: LuaTable| {
+            for (index, check) in checks.sequence_values::<LuaValue>().enumerate() {
+                if !Self::check_result("all", index + 1, check?)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        })?;
+        globals.set("all", all_check)?;
 
-        // Insert at the beginning of searchers table (index 1)
-        searchers.raw_insert(1, custom_searcher)
-            .map_err(|e| anyhow::anyhow!("Failed to insert custom searcher: {}", e))?;
+        // none(checks: table) -> bool
+        let none_check = lua.create_function(|_, checks: LuaTable| {
+            for (index, check) in checks.sequence_values::<LuaValue>().enumerate() {
+                if Self::check_result("none", index + 1, check?)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        })?;
+        globals.set("none", none_check)?;
 
-        Ok(())
-    }
+        // at_least(n: number, checks: table) -> bool
+        let at_least = lua.create_function(|_, (n, checks): (usize, LuaTable)| {
+            let (trues, _) = Self::count_checks("at_least", &checks, |trues, falses, total| {
+                trues >= n || total - falses < n
+            })?;
+            Ok(trues >= n)
+        })?;
+        globals.set("at_least", at_least)?;
 
-    /// Execute a Lua script and return the result
-    pub fn execute(&self, script: &str) -> Result<LuaGatekeeperResult> {
-        // Parse TTL from comment if present (-- ttl: 3600)
-        let ttl = Self::parse_ttl_comment(script);
+        // at_most(n: number, checks: table) -> bool
+        let at_most = lua.create_function(|_, (n, checks): (usize, LuaTable)| {
+            let (trues, _) = Self::count_checks("at_most", &checks, |trues, _, _| trues > n)?;
+            Ok(trues <= n)
+        })?;
+        globals.set("at_most", at_most)?;
 
-        // Execute the Lua script
-        let result: LuaValue = self
-            .lua
-            .load(script)
-            .eval()
-            .map_err(|e| anyhow::anyhow!("Lua execution failed:\n{}\nError: {}", Self::format_script(script), e))?;
+        // exactly(n: number, checks: table) -> bool
+        let exactly = lua.create_function(|_, (n, checks): (usize, LuaTable)| {
+            let (trues, _) = Self::count_checks("exactly", &checks, |trues, falses, total| {
+                trues > n || total - falses < n
+            })?;
+            Ok(trues == n)
+        })?;
+        globals.set("exactly", exactly)?;
 
-        // Extract result
-        match result {
-            // Simple boolean return
-            LuaValue::Boolean(value) => Ok(LuaGatekeeperResult { value, ttl }),
+        // xor(checks: table) -> bool
+        // True if exactly one check is true
+        let xor = lua.create_function(|_, checks: LuaTable| {
+            let (trues, _) = Self::count_checks("xor", &checks, |trues, _, _| trues > 1)?;
+            Ok(trues == 1)
+        })?;
+        globals.set("xor", xor)?;
 
-            // Table with value and optional ttl
-            LuaValue::Table(table) => {
-                let value = table
-                    .get::<_, bool>("value")
-                    .map_err(|_| anyhow::anyhow!("Table must contain a 'value' field of type boolean"))?;
-                let table_ttl = table.get::<_, Option<u64>>("ttl").ok().flatten();
-                Ok(LuaGatekeeperResult {
-                    value,
-                    ttl: table_ttl.or(ttl),
-                })
+        // majority(checks: table) -> bool
+        // True if more than half of the checks are true
+        let majority = lua.create_function(|_, checks: LuaTable| {
+            let (trues, total) = Self::count_checks("majority", &checks, |trues, falses, total| {
+                trues * 2 > total || falses * 2 >= total
+            })?;
+            Ok(trues * 2 > total)
+        })?;
+        globals.set("majority", majority)?;
+
+        // score(checks: table) -> number
+        // Sum of the weights of the true checks, given as { {check, weight}, ... }
+        let score = lua.create_function(|_, checks: LuaTable| {
+            let mut total = 0.0;
+            for (index, entry) in checks.sequence_values::<LuaValue>().enumerate() {
+                let index = index + 1;
+                let LuaValue::Table(entry) = entry? else {
+                    return Err(LuaError::RuntimeError(format!(
+                        "score(): check #{} must be a {{check, weight}} pair",
+                        index
+                    )));
+                };
+                let weight = match entry.raw_get::<_, LuaValue>(2)? {
+                    LuaValue::Integer(weight) => weight as f64,
+                    LuaValue::Number(weight) => weight,
+                    other => {
+                        return Err(LuaError::RuntimeError(format!(
+                            "score(): weight of check #{} is {}, expected a number",
+                            index,
+                            other.type_name()
+                        )));
+                    }
+                };
+                if Self::check_result("score", index, entry.raw_get(1)?)? {
+                    total += weight;
+                }
             }
+            Ok(total)
+        })?;
+        globals.set("score", score)?;
 
-            _ => anyhow::bail!(
-                "Lua script must return a boolean or table with 'value' field.\nGot: {:?}",
-                result
-            ),
-        }
+        Ok(())
     }
 
-    /// Parse TTL from comment like: -- ttl: 3600
-    fn parse_ttl_comment(script: &str) -> Option<u64> {
-        let re = Regex::new(r"^--\s*ttl:\s*(\d+)").ok()?;
-        for line in script.lines() {
-            if let Some(captures) = re.captures(line.trim()) {
-                if let Some(ttl_str) = captures.get(1) {
-                    return ttl_str.as_str().parse::<u64>().ok();
-                }
+    /// Count true checks in order until `done(trues, falses, total)` says the
+    /// answer is decided. Returns the true count and the number of checks
+    fn count_checks(
+        function: &str,
+        checks: &LuaTable,
+        done: impl Fn(usize, usize, usize) -> bool,
+    ) -> LuaResult<(usize, usize)> {
+        let total = checks.raw_len();
+        let (mut trues, mut falses) = (0, 0);
+        for (index, check) in checks.clone().sequence_values::<LuaValue>().enumerate() {
+            if done(trues, falses, total) {
+                break;
+            }
+            if Self::check_result(function, index + 1, check?)? {
+                trues += 1;
+            } else {
+                falses += 1;
             }
         }
-        None
+        Ok((trues, total))
     }
 
-    /// Format script with line numbers for error messages
-    fn format_script(script: &str) -> String {
-        script
-            .lines()
-            .enumerate()
-            .map(|(i, line)| format!("{:3} | {}", i + 1, line))
-            .collect::<Vec<_>>()
-            .join("\n")
+    /// Evaluate one element of a combinator's checks, calling it if it is a function.
+    /// `index` is the 1-based position used in error messages
+    fn check_result(function: &str, index: usize, check: LuaValue) -> LuaResult<bool> {
+        match check {
+            LuaValue::Boolean(value) => Ok(value),
+            LuaValue::Function(thunk) => match thunk.call::<_, LuaValue>(())? {
+                LuaValue::Boolean(value) => Ok(value),
+                other => Err(LuaError::RuntimeError(format!(
+                    "{}(): check #{} returned {}, expected a boolean",
+                    function,
+                    index,
+                    other.type_name()
+                ))),
+            },
+            other => Err(LuaError::RuntimeError(format!(
+                "{}(): check #{} is {}, expected a boolean or a function",
+                function,
+                index,
+                other.type_name()
+            ))),
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    fn register_user_functions(lua: &Lua) -> Result<()> {
+        let globals = lua.globals();
 
-    #[test]
-    fn test_file_exists() {
-        let executor = LuaExecutor::new().unwrap();
-        let result = executor
-            .execute(r#"return file_exists("/etc/passwd")"#)
-            .unwrap();
-        // This file should exist on Linux systems
-        if cfg!(target_os = "linux") {
-            assert_eq!(result.value, true);
-        }
+        // user(name: string | table) -> bool
+        let user = lua.create_function(|_, names: LuaValue| {
+            let names = Self::string_list(names, "user")?;
+            let current = facts::user::username(&facts::root());
+            Ok(current.is_some_and(|user| names.contains(&user)))
+        })?;
+        globals.set("user", user)?;
+
+        // uid() -> number | nil
+        let uid = lua.create_function(|_, ()| {
+            Ok(facts::user::current(&facts::root()).map(|creds| creds.uid))
+        })?;
+        globals.set("uid", uid)?;
+
+        // in_group(name: string) -> bool
+        let in_group =
+            lua.create_function(|_, name: String| Ok(facts::user::in_group(&facts::root(), &name)))?;
+        globals.set("in_group", in_group)?;
+
+        // is_root() -> bool
+        // Checks the effective uid, so it's true under setuid and sudo
+        let is_root = lua.create_function(|_, ()| {
+            Ok(facts::user::current(&facts::root()).is_some_and(|creds| creds.euid == 0))
+        })?;
+        globals.set("is_root", is_root)?;
+
+        Ok(())
     }
 
-    #[test]
-    fn test_file_not_exists() {
-        let executor = LuaExecutor::new().unwrap();
-        let result = executor
-            .execute(r#"return file_exists("/nonexistent/path/12345")"#)
-            .unwrap();
-        assert_eq!(result.value, false);
+    fn register_distro_functions(lua: &Lua) -> Result<()> {
+        let globals = lua.globals();
+
+        // distro(name: string | table) -> bool
+        // Matches the os-release ID, e.g. distro("debian") or distro({"fedora", "rhel"})
+        let distro = lua.create_function(|_, names: LuaValue| {
+            let names = Self::string_list(names, "distro")?;
+            let release = facts::os_release::read(&facts::root()).unwrap_or_default();
+            Ok(release
+                .id
+                .is_some_and(|id| names.iter().any(|name| name.eq_ignore_ascii_case(&id))))
+        })?;
+        globals.set("distro", distro)?;
+
+        // distro_like(name: string) -> bool
+        // Matches the os-release ID or any entry of ID_LIKE, e.g. distro_like("rhel") on Rocky
+        let distro_like = lua.create_function(|_, name: String| {
+            let name = name.to_lowercase();
+            let release = facts::os_release::read(&facts::root()).unwrap_or_default();
+            Ok(release.id.as_deref() == Some(name.as_str()) || release.id_like.contains(&name))
+        })?;
+        globals.set("distro_like", distro_like)?;
+
+        // distro_version_at_least(version: string) -> bool
+        // Compares os-release VERSION_ID as a version, so "22.10" >= "22.04"
+        let distro_version_at_least = lua.create_function(|_, minimum: String| {
+            let minimum = facts::version::Version::parse(&minimum).map_err(runtime_error)?;
+            let release = facts::os_release::read(&facts::root()).unwrap_or_default();
+            Ok(release
+                .version_id
+                .and_then(|version| facts::version::Version::parse(&version).ok())
+                .is_some_and(|version| version >= minimum))
+        })?;
+        globals.set("distro_version_at_least", distro_version_at_least)?;
+
+        // kernel_version() -> string | nil
+        let kernel_version =
+            lua.create_function(|_, ()| Ok(facts::os_release::kernel_version(&facts::root())))?;
+        globals.set("kernel_version", kernel_version)?;
+
+        Ok(())
     }
 
-    #[test]
-    fn test_os_check() {
-        let executor = LuaExecutor::new().unwrap();
-        let result = executor.execute(r#"return os("linux")"#).unwrap();
-        assert_eq!(result.value, cfg!(target_os = "linux"));
+    fn register_version_functions(lua: &Lua) -> Result<()> {
+        let globals = lua.globals();
+
+        // version(value: string) -> version
+        // Supports <, <=, >, >= against versions or strings, and == between versions
+        let version = lua.create_function(|_, value: LuaValue| {
+            Ok(LuaVersion(LuaVersion::coerce(&value)?))
+        })?;
+        globals.set("version", version)?;
+
+        // command_version(command: string, args?: string | table, timeout_ms?: number) -> version | nil
+        // Runs the command and parses the first version-like token of its output.
+        // Memoized for the rest of the run, so each command runs once per sync
+        let command_version = lua.create_function(
+            |_, (command, args, timeout_ms): (String, Option<LuaValue>, Option<u64>)| {
+                let args = match args {
+                    Some(args) => Self::string_list(args, "command_version")?,
+                    None => Vec::new(),
+                };
+                let timeout = Duration::from_millis(timeout_ms.unwrap_or(2_000).min(10_000));
+                let key = format!("command_version:{}:{:?}:{}", command, args, timeout.as_millis());
+                let found = facts::probe::memoize(&key, || {
+                    let (stdout, stderr) = facts::probe::command_output(&command, &args, timeout)?;
+                    // Some tools (java, older gcc) report their version on stderr
+                    facts::version::extract(&stdout)
+                        .or_else(|| facts::version::extract(&stderr))
+                        .map(|version| version.to_string())
+                });
+                debug!("command_version(): {} -> {:?}", command, found);
+                Ok(found
+                    .and_then(|version| facts::version::Version::parse(&version).ok())
+                    .map(LuaVersion))
+            },
+        )?;
+        globals.set("command_version", command_version)?;
+
+        Ok(())
     }
 
-    #[test]
-    fn test_any_combinator() {
-        let executor = LuaExecutor::new().unwrap();
-        let result = executor
-            .execute(
-                r#"
-            return any({
-                file_exists("/nonexistent1"),
-                file_exists("/etc/passwd"),
-            })
-        "#,
-            )
-            .unwrap();
-        if cfg!(target_os = "linux") {
-            assert_eq!(result.value, true);
+    fn register_hardware_functions(lua: &Lua) -> Result<()> {
+        let globals = lua.globals();
+
+        // arch(name: string | table) -> bool
+        // Aliases are normalized, so arch("arm64") matches an aarch64 build
+        let arch = lua.create_function(|_, names: LuaValue| {
+            let names = Self::string_list(names, "arch")?;
+            let current = facts::hardware::arch();
+            Ok(names
+                .iter()
+                .any(|name| facts::hardware::normalize_arch(name) == current))
+        })?;
+        globals.set("arch", arch)?;
+
+        // cpu_count() -> number
+        let cpu_count =
+            lua.create_function(|_, ()| Ok(facts::hardware::cpu_count(&facts::root())))?;
+        globals.set("cpu_count", cpu_count)?;
+
+        // memory_total_mb() -> number | nil
+        let memory_total_mb =
+            lua.create_function(|_, ()| Ok(facts::hardware::memory_total_mb(&facts::root())))?;
+        globals.set("memory_total_mb", memory_total_mb)?;
+
+        // has_battery() -> bool
+        let has_battery =
+            lua.create_function(|_, ()| Ok(facts::hardware::has_battery(&facts::root())))?;
+        globals.set("has_battery", has_battery)?;
+
+        Ok(())
+    }
+
+    fn register_environment_functions(lua: &Lua) -> Result<()> {
+        let globals = lua.globals();
+
+        // Each detector returns the signal that matched, if any
+        type Detector = fn() -> Option<String>;
+        let detectors: [(&str, Detector); 5] = [
+            ("in_container", || facts::environment::container_signal(&facts::root())),
+            ("in_wsl", || facts::environment::wsl_signal(&facts::root(), process_env)),
+            ("in_vm", || facts::environment::vm_signal(&facts::root())),
+            ("in_ssh", || facts::environment::ssh_signal(process_env)),
+            ("in_tmux", || facts::environment::tmux_signal(process_env)),
+        ];
+
+        // in_container(), in_wsl(), in_vm(), in_ssh(), in_tmux() -> bool
+        for (name, detect) in detectors {
+            let function = lua.create_function(move |_, ()| match detect() {
+                Some(signal) => {
+                    debug!("{}() matched: {}", name, signal);
+                    Ok(true)
+                }
+                None => {
+                    debug!("{}() matched no signals", name);
+                    Ok(false)
+                }
+            })?;
+            globals.set(name, function)?;
         }
+
+        Ok(())
     }
 
-    #[test]
-    fn test_all_combinator() {
-        let executor = LuaExecutor::new().unwrap();
-        let result = executor
+    fn register_network_functions(lua: &Lua) -> Result<()> {
+        let globals = lua.globals();
+
+        // interface_up(name: string) -> bool
+        let interface_up = lua.create_function(|_, name: String| {
+            Ok(facts::network::interface_up(&facts::root(), &name))
+        })?;
+        globals.set("interface_up", interface_up)?;
+
+        // has_ip_in(cidr: string | table) -> bool
+        // True if any local address falls inside any of the given ranges
+        let has_ip_in = lua.create_function(|_, ranges: LuaValue| {
+            // Reject a bad range even on hosts with no addresses to check it against
+            let ranges = Self::string_list(ranges, "has_ip_in")?
+                .iter()
+                .map(|range| facts::network::Cidr::parse(range))
+                .collect::<Result<Vec<_>>>()
+                .map_err(runtime_error)?;
+            let addresses = facts::network::local_addresses(&facts::root());
+            for range in &ranges {
+                for addr in &addresses {
+                    if range.contains(*addr) {
+                        debug!("has_ip_in(): {} is in {}", addr, range);
+                        return Ok(true);
+                    }
+                }
+            }
+            Ok(false)
+        })?;
+        globals.set("has_ip_in", has_ip_in)?;
+
+        // default_route_via(interface: string) -> bool
+        let default_route_via = lua.create_function(|_, name: String| {
+            Ok(facts::network::default_route_interfaces(&facts::root()).contains(&name))
+        })?;
+        globals.set("default_route_via", default_route_via)?;
+
+        // port_listening(port: number) -> bool
+        let port_listening = lua.create_function(|_, port: u16| {
+            Ok(facts::network::port_listening(&facts::root(), port))
+        })?;
+        globals.set("port_listening", port_listening)?;
+
+        // tcp_connect(host: string, port: number, timeout_ms?: number) -> bool
+        // Memoized for the rest of the run, so each target is probed once per sync
+        let tcp_connect =
+            lua.create_function(|_, (host, port, timeout_ms): (String, u16, Option<u64>)| {
+                let timeout = Self::probe_timeout(timeout_ms);
+                let key = format!("tcp_connect:{}:{}:{}", host, port, timeout.as_millis());
+                Ok(facts::probe::memoize(&key, || {
+                    facts::probe::tcp_connect(&host, port, timeout)
+                }))
+            })?;
+        globals.set("tcp_connect", tcp_connect)?;
+
+        // unix_socket_alive(path: string, timeout_ms?: number) -> bool
+        let unix_socket_alive =
+            lua.create_function(|lua, (path, timeout_ms): (String, Option<u64>)| {
+                let Some(path) = Self::resolve_path(lua, &path) else {
+                    return Ok(false);
+                };
+                let timeout = Self::probe_timeout(timeout_ms);
+                let key = format!("unix_socket_alive:{}:{}", path.display(), timeout.as_millis());
+                Ok(facts::probe::memoize(&key, || {
+                    facts::probe::unix_socket_alive(&path, timeout)
+                }))
+            })?;
+        globals.set("unix_socket_alive", unix_socket_alive)?;
+
+        Ok(())
+    }
+
+    fn register_system_functions(lua: &Lua) -> Result<()> {
+        let globals = lua.globals();
+
+        // process_running(name: string | table) -> bool
+        // Matches the process name, the basename of its command ("tailscaled") or an
+        // interpreter's script ("foo.py" for `python3 foo.py`)
+        let process_running = lua.create_function(|_, names: LuaValue| {
+            let names = Self::string_list(names, "process_running")?;
+            let root = facts::root();
+            Ok(names
+                .iter()
+                .any(|name| facts::process::process_running(&root, name)))
+        })?;
+        globals.set("process_running", process_running)?;
+
+        // is_mounted(path: string) -> bool
+        // True if the path is itself a mount point
+        let is_mounted = lua.create_function(|lua, path: String| {
+            let Some(path) = Self::resolve_path(lua, &path) else {
+                return Ok(false);
+            };
+            let mounts = facts::mounts::read(&facts::root());
+            Ok(facts::mounts::is_mounted(&mounts, &path.to_string_lossy()))
+        })?;
+        globals.set("is_mounted", is_mounted)?;
+
+        // mount_type(path: string, fs_type: string | table) -> bool
+        // Checks the filesystem the path lives on; "nfs" also matches "nfs4",
+        // and "sshfs" matches "fuse.sshfs"
+        let mount_type = lua.create_function(|lua, (path, fs_types): (String, LuaValue)| {
+            let fs_types = Self::string_list(fs_types, "mount_type")?;
+            let Some(path) = Self::resolve_path(lua, &path) else {
+                return Ok(false);
+            };
+            let mounts = facts::mounts::read(&facts::root());
+            let Some(mount) = facts::mounts::containing(&mounts, &path.to_string_lossy()) else {
+                return Ok(false);
+            };
+            debug!("mount_type(): {} is on {} ({})", path.display(), mount.mount_point, mount.fs_type);
+            Ok(fs_types
+                .iter()
+                .any(|wanted| facts::mounts::fs_type_matches(&mount.fs_type, wanted)))
+        })?;
+        globals.set("mount_type", mount_type)?;
+
+        Ok(())
+    }
+
+    fn register_git_functions(lua: &Lua) -> Result<()> {
+        let globals = lua.globals();
+
+        // in_git_repo(path?: string) -> bool
+        let in_git_repo = lua.create_function(|lua, path: Option<String>| {
+            Ok(Self::git_repository(lua, path)?.is_some())
+        })?;
+        globals.set("in_git_repo", in_git_repo)?;
+
+        // git_remote_matches(pattern: string, path?: string) -> bool
+        // Regex match against the URL of any remote
+        let git_remote_matches =
+            lua.create_function(|lua, (pattern, path): (String, Option<String>)| {
+                let re = Regex::new(&pattern).map_err(|e| {
+                    LuaError::RuntimeError(format!("Invalid remote regex '{}': {}", pattern, e))
+                })?;
+                Ok(Self::git_repository(lua, path)?
+                    .is_some_and(|repo| repo.remote_urls().iter().any(|url| re.is_match(url))))
+            })?;
+        globals.set("git_remote_matches", git_remote_matches)?;
+
+        // git_branch(path?: string) -> string | nil
+        // nil outside a repository or with a detached HEAD
+        let git_branch = lua.create_function(|lua, path: Option<String>| {
+            Ok(Self::git_repository(lua, path)?.and_then(|repo| repo.branch()))
+        })?;
+        globals.set("git_branch", git_branch)?;
+
+        Ok(())
+    }
+
+    /// Repository containing `path`, or the current directory if none is given
+    fn git_repository(
+        lua: &Lua,
+        path: Option<String>,
+    ) -> LuaResult<Option<facts::git::Repository>> {
+        let start = match path {
+            Some(path) => match Self::resolve_path(lua, &path) {
+                Some(path) => path,
+                None => return Ok(None),
+            },
+            None => std::env::current_dir().map_err(runtime_error)?,
+        };
+        Ok(facts::git::discover(&start))
+    }
+
+    fn register_time_functions(lua: &Lua) -> Result<()> {
+        let globals = lua.globals();
+
+        // weekday(day: string | table) -> bool, or weekday(first: string, last: string) -> bool
+        // Two days form an inclusive range, so weekday("mon", "fri") means Monday to Friday
+        let weekday = lua.create_function(|lua, (days, last): (LuaValue, Option<String>)| {
+            let clock = Self::clock(lua);
+            let today = clock.now().weekday();
+            let parse = |day: &str| facts::time::parse_weekday(day).map_err(runtime_error);
+
+            let matches = match last {
+                Some(last) => {
+                    let first = Self::string_list(days, "weekday")?;
+                    if first.len() != 1 {
+                        return Err(LuaError::RuntimeError(
+                            "weekday(first, last) expects two day names".into(),
+                        ));
+                    }
+                    facts::time::weekday_in_range(today, parse(&first[0])?, parse(&last)?)
+                }
+                None => {
+                    let mut matches = false;
+                    for day in Self::string_list(days, "weekday")? {
+                        matches |= parse(&day)? == today;
+                    }
+                    matches
+                }
+            };
+
+            Self::record_boundary(lua, clock.next_midnight());
+            Ok(matches)
+        })?;
+        globals.set("weekday", weekday)?;
+
+        // time_between(start: string, end: string) -> bool
+        // Local time in [start, end), wrapping past midnight when end < start
+        let time_between = lua.create_function(|lua, (start, end): (String, String)| {
+            let start = facts::time::parse_time(&start).map_err(runtime_error)?;
+            let end = facts::time::parse_time(&end).map_err(runtime_error)?;
+            let (inside, boundary) = facts::time::time_between(&Self::clock(lua), start, end);
+            Self::record_boundary(lua, boundary);
+            Ok(inside)
+        })?;
+        globals.set("time_between", time_between)?;
+
+        // date_before(date: string) -> bool
+        // True until local midnight at the start of the given YYYY-MM-DD date
+        let date_before = lua.create_function(|lua, date: String| {
+            let date = facts::time::parse_date(&date).map_err(runtime_error)?;
+            let (before, boundary) = facts::time::date_before(&Self::clock(lua), date);
+            if let Some(boundary) = boundary {
+                Self::record_boundary(lua, boundary);
+            }
+            Ok(before)
+        })?;
+        globals.set("date_before", date_before)?;
+
+        // timezone(name: string | table) -> bool
+        let timezone = lua.create_function(|lua, names: LuaValue| {
+            let names = Self::string_list(names, "timezone")?;
+            let clock = Self::clock(lua);
+            Ok(clock
+                .timezone()
+                .is_some_and(|tz| names.iter().any(|name| name == tz)))
+        })?;
+        globals.set("timezone", timezone)?;
+
+        Ok(())
+    }
+
+    fn register_rollout_functions(lua: &Lua) -> Result<()> {
+        let globals = lua.globals();
+
+        // machine_id() -> string | nil
+        let machine_id =
+            lua.create_function(|_, ()| Ok(facts::identity::machine_id(&facts::root())))?;
+        globals.set("machine_id", machine_id)?;
+
+        // rollout(flag: string, percent: number, opts?: { key = string, salt = string }) -> bool
+        // Stable per machine: the same flag and percentage always give the same answer
+        let rollout = lua.create_function(
+            |_, (flag, percent, opts): (String, f64, Option<LuaTable>)| {
+                let (key, salt) = match opts {
+                    Some(opts) => (
+                        opts.get::<_, Option<String>>("key")?,
+                        opts.get::<_, Option<String>>("salt")?,
+                    ),
+                    None => (None, None),
+                };
+                let key = match key.or_else(|| facts::identity::machine_id(&facts::root())) {
+                    Some(key) => key,
+                    None => {
+                        return Err(LuaError::RuntimeError(format!(
+                            "rollout('{}'): could not determine a machine id",
+                            flag
+                        )));
+                    }
+                };
+                let enabled =
+                    facts::identity::rollout(&flag, percent, &key, salt.as_deref().unwrap_or(""))
+                        .map_err(runtime_error)?;
+                debug!("rollout('{}', {}) = {}", flag, percent, enabled);
+                Ok(enabled)
+            },
+        )?;
+        globals.set("rollout", rollout)?;
+
+        Ok(())
+    }
+
+    fn register_log_functions(lua: &Lua) -> Result<()> {
+        let globals = lua.globals();
+
+        // log.debug/info/warn(...): arguments are joined like print() and sent to tracing,
+        // inside the span of the gatekeeper being evaluated
+        let log = lua.create_table()?;
+        let debug = lua.create_function(|lua, args: LuaMultiValue| {
+            tracing::debug!(target: SCRIPT_LOG_TARGET, "{}", Self::join_args(lua, args)?);
+            Ok(())
+        })?;
+        log.set("debug", debug)?;
+        let info = lua.create_function(|lua, args: LuaMultiValue| {
+            tracing::info!(target: SCRIPT_LOG_TARGET, "{}", Self::join_args(lua, args)?);
+            Ok(())
+        })?;
+        log.set("info", info)?;
+        let warn = lua.create_function(|lua, args: LuaMultiValue| {
+            tracing::warn!(target: SCRIPT_LOG_TARGET, "{}", Self::join_args(lua, args)?);
+            Ok(())
+        })?;
+        log.set("warn", warn)?;
+        globals.set("log", log)?;
+
+        // print(...) writes to stderr, since stdout carries the gatekeeper's value
+        let print = lua.create_function(|lua, args: LuaMultiValue| {
+            eprintln!("{}", Self::join_args(lua, args)?);
+            Ok(())
+        })?;
+        globals.set("print", print)?;
+
+        Ok(())
+    }
+
+    /// Convert arguments with `tostring` and join them with tabs, as Lua's print does
+    fn join_args(lua: &Lua, args: LuaMultiValue) -> LuaResult<String> {
+        let tostring: LuaFunction = lua.globals().get("tostring")?;
+        let parts = args
+            .into_iter()
+            .map(|arg| tostring.call::<_, String>(arg))
+            .collect::<LuaResult<Vec<_>>>()?;
+        Ok(parts.join("\t"))
+    }
+
+    fn register_cache_functions(lua: &Lua) -> Result<()> {
+        let globals = lua.globals();
+
+        // cached(name: string, default?: any) -> value
+        // Last cached value of another entry (e.g. one set with `dotgk set`), without evaluating it
+        let cached = lua.create_function(|lua, (name, default): (String, LuaValue)| {
+            match Self::read_cached(lua, &name) {
+                Some(value) => Self::value_to_lua(lua, &value),
+                None => Ok(default),
+            }
+        })?;
+        globals.set("cached", cached)?;
+
+        // is_set(name: string) -> bool
+        let is_set = lua.create_function(|lua, name: String| Ok(Self::read_cached(lua, &name).is_some()))?;
+        globals.set("is_set", is_set)?;
+
+        // previous() -> value, ts | nil
+        // This gatekeeper's last cached value and when it was evaluated
+        let previous = lua.create_function(|lua, ()| {
+            let Some(previous) = lua.app_data_ref::<PreviousEntry>().map(|entry| entry.0.clone()) else {
+                return Ok(LuaMultiValue::from_vec(vec![LuaValue::Nil]));
+            };
+            Ok(LuaMultiValue::from_vec(vec![
+                Self::value_to_lua(lua, &previous.value)?,
+                LuaValue::Integer(previous.ts as i64),
+            ]))
+        })?;
+        globals.set("previous", previous)?;
+
+        Ok(())
+    }
+
+    /// Look up a live cache entry's value, remembering what was seen so the
+    /// result is invalidated when that entry changes
+    fn read_cached(lua: &Lua, name: &str) -> Option<GatekeeperValue> {
+        let cache_path = lua.app_data_ref::<CachePath>().map(|path| path.0.clone());
+        let value = crate::cache::lookup_entry(name, cache_path).map(|entry| entry.value);
+        debug!("cached('{}') = {:?}", name, value);
+        Self::record_cache_reads(lua, [(name.to_string(), value.clone())]);
+        value
+    }
+
+    /// Note that the result being computed depends on the cached values of other entries
+    fn record_cache_reads(
+        lua: &Lua,
+        reads: impl IntoIterator<Item = (String, Option<GatekeeperValue>)>,
+    ) {
+        if let Some(mut cache_reads) = lua.app_data_mut::<CacheReads>() {
+            for (name, value) in reads {
+                cache_reads.0.entry(name).or_insert(value);
+            }
+        }
+    }
+
+    fn register_data_functions(lua: &Lua) -> Result<()> {
+        let globals = lua.globals();
+
+        let formats = [
+            ("json", facts::data::Format::Json),
+            ("toml", facts::data::Format::Toml),
+            ("yaml", facts::data::Format::Yaml),
+            ("ini", facts::data::Format::Ini),
+        ];
+
+        for (prefix, format) in formats {
+            // json_get(path: string, key_path: string) -> value | nil (likewise toml_, yaml_, ini_)
+            let get_name = format!("{}_get", prefix);
+            let name = get_name.clone();
+            let get = lua.create_function(move |lua, (path, key_path): (String, String)| {
+                match Self::query_data_file(lua, &name, format, &path, &key_path)? {
+                    Some(value) => Self::json_to_lua(lua, &value),
+                    None => Ok(LuaValue::Nil),
+                }
+            })?;
+            globals.set(get_name, get)?;
+
+            // json_eq(path: string, key_path: string, expected: any) -> bool (likewise toml_, yaml_, ini_)
+            let eq_name = format!("{}_eq", prefix);
+            let name = eq_name.clone();
+            let eq = lua.create_function(
+                move |lua, (path, key_path, expected): (String, String, LuaValue)| {
+                    let actual = Self::query_data_file(lua, &name, format, &path, &key_path)?;
+                    Ok(Self::json_equals(actual.as_ref(), &expected))
+                },
+            )?;
+            globals.set(eq_name, eq)?;
+        }
+
+        Ok(())
+    }
+
+    /// Load a data file and look up a key path, naming the file and key in any error
+    fn query_data_file(
+        lua: &Lua,
+        function: &str,
+        format: facts::data::Format,
+        path: &str,
+        key_path: &str,
+    ) -> LuaResult<Option<serde_json::Value>> {
+        let describe = |e: anyhow::Error| {
+            LuaError::RuntimeError(format!(
+                "{}('{}', '{}'): {:#}",
+                function, path, key_path, e
+            ))
+        };
+        let Some(resolved) = Self::resolve_path(lua, path) else {
+            return Ok(None);
+        };
+        let Some(document) = facts::data::load(&resolved, format).map_err(describe)? else {
+            return Ok(None);
+        };
+        Ok(facts::data::query(&document, key_path)
+            .map_err(describe)?
+            .cloned())
+    }
+
+    fn json_to_lua<'lua>(lua: &'lua Lua, value: &serde_json::Value) -> LuaResult<LuaValue<'lua>> {
+        use serde_json::Value;
+        Ok(match value {
+            Value::Null => LuaValue::Nil,
+            Value::Bool(b) => LuaValue::Boolean(*b),
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => LuaValue::Integer(i),
+                None => LuaValue::Number(n.as_f64().unwrap_or(f64::NAN)),
+            },
+            Value::String(s) => LuaValue::String(lua.create_string(s)?),
+            Value::Array(items) => {
+                let table = lua.create_table()?;
+                for (i, item) in items.iter().enumerate() {
+                    table.raw_set(i + 1, Self::json_to_lua(lua, item)?)?;
+                }
+                LuaValue::Table(table)
+            }
+            Value::Object(map) => {
+                let table = lua.create_table()?;
+                for (key, item) in map {
+                    table.raw_set(key.as_str(), Self::json_to_lua(lua, item)?)?;
+                }
+                LuaValue::Table(table)
+            }
+        })
+    }
+
+    /// Compare a document value with a Lua value, treating string forms as
+    /// equal so INI values (always strings) compare naturally with numbers and booleans
+    fn json_equals(actual: Option<&serde_json::Value>, expected: &LuaValue) -> bool {
+        use serde_json::Value;
+        let actual = match actual {
+            None | Some(Value::Null) => return matches!(expected, LuaValue::Nil),
+            Some(value) => value,
+        };
+        match expected {
+            LuaValue::Boolean(b) => match actual {
+                Value::Bool(v) => v == b,
+                Value::String(s) => s.eq_ignore_ascii_case(&b.to_string()),
+                _ => false,
+            },
+            LuaValue::Integer(_) | LuaValue::Number(_) => {
+                let expected = match expected {
+                    LuaValue::Integer(i) => *i as f64,
+                    LuaValue::Number(n) => *n,
+                    _ => unreachable!(),
+                };
+                match actual {
+                    Value::Number(n) => n.as_f64() == Some(expected),
+                    Value::String(s) => s.trim().parse::<f64>() == Ok(expected),
+                    _ => false,
+                }
+            }
+            LuaValue::String(expected) => {
+                let Ok(expected) = expected.to_str() else {
+                    return false;
+                };
+                match actual {
+                    Value::String(s) => s == expected,
+                    Value::Number(n) => n.to_string() == expected,
+                    Value::Bool(b) => b.to_string() == expected,
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    fn script_dir(lua: &Lua) -> Option<PathBuf> {
+        lua.app_data_ref::<ScriptDir>().map(|dir| dir.0.clone())
+    }
+
+    /// Expand a path argument, or `None` if it references an unset variable
+    fn resolve_path(lua: &Lua, path: &str) -> Option<PathBuf> {
+        facts::paths::expand(path, Self::script_dir(lua).as_deref())
+    }
+
+    fn clock(lua: &Lua) -> Clock {
+        lua.app_data_ref::<Clock>()
+            .map(|clock| clock.clone())
+            .unwrap_or_else(Clock::from_env)
+    }
+
+    /// Note that the result being computed could change at unix time `at`
+    fn record_boundary(lua: &Lua, at: i64) {
+        if let Some(mut next) = lua.app_data_mut::<NextBoundary>() {
+            next.0 = Some(next.0.map_or(at, |current| current.min(at)));
+        }
+    }
+
+    /// Note that the result being computed depends on the contents of `paths`
+    fn record_dependencies(lua: &Lua, paths: impl IntoIterator<Item = PathBuf>) {
+        if let Some(mut dependencies) = lua.app_data_mut::<Dependencies>() {
+            for path in paths {
+                if !dependencies.0.contains(&path) {
+                    dependencies.0.push(path);
+                }
+            }
+        }
+    }
+
+    fn register_require_searcher(lua: &Lua, context: std::rc::Rc<EvaluationContext>) -> Result<()> {
+        // Get package.searchers table
+        let package: LuaTable = lua.globals().get("package")
+            .map_err(|e| anyhow::anyhow!("Failed to get package table: {}", e))?;
+        let searchers: LuaTable = package.get("searchers")
+            .map_err(|e| anyhow::anyhow!("Failed to get package.searchers: {}", e))?;
+
+        // Create custom searcher function
+        let custom_searcher = lua.create_function(move |lua_ctx, module_name: String| {
+            // Convert "meta.devserver" -> "meta/devserver"
+            let gk_name = module_name.replace('.', "/");
+
+            // Determine paths to try:
+            // For "meta" -> try ["meta", "meta/init"]
+            // For "meta/devserver" -> try ["meta/devserver"]
+            let paths_to_try: Vec<String> = if !gk_name.contains('/') {
+                // Simple name like "meta" - try both direct and init.lua
+                vec![gk_name.clone(), format!("{}/init", gk_name)]
+            } else {
+                // Nested name like "meta/devserver" - just try as-is
+                vec![gk_name.clone()]
+            };
+
+            // Try each path
+            for path in &paths_to_try {
+                // Check if gatekeeper file exists
+                if let Ok(gk_path) = crate::gatekeeper::get_gatekeeper_path(path)
+                    && gk_path.exists()
+                {
+                    // Found it! Check for circular dependency
+                    context
+                        .visit(path)
+                        .map_err(|e| LuaError::RuntimeError(e.to_string()))?;
+
+                    // Create loader function that will be called by require()
+                    let path_clone = path.clone();
+                    let context_clone = context.clone();
+
+                    let loader = lua_ctx.create_function(move |lua, _: ()| {
+                        // Load and evaluate the gatekeeper with the caller's variables;
+                        // this auto-detects init.lua and sets context
+                        let vars = lua.app_data_ref::<Args>().map(|args| args.0.clone()).unwrap_or_default();
+                        match crate::gatekeeper::load_and_evaluate_gatekeeper_with_previous(
+                            &path_clone,
+                            None,
+                            &vars,
+                        ) {
+                            Ok(result) => {
+                                context_clone.leave(&path_clone);
+                                // Changes to it, or anything it requires, invalidate this gatekeeper
+                                Self::record_dependencies(
+                                    lua,
+                                    std::iter::once(gk_path.clone()).chain(result.dependencies),
+                                );
+                                Self::record_cache_reads(lua, result.cache_dependencies);
+                                // The requiring gatekeeper can't outlive what it depends on
+                                if let Some(ttl) = result.ttl {
+                                    let expires_at = Self::clock(lua).timestamp() + ttl as i64;
+                                    Self::record_boundary(lua, expires_at);
+                                }
+                                Self::value_to_lua(lua, &result.value)
+                            }
+                            Err(e) => {
+                                context_clone.leave(&path_clone);
+                                // Pass script errors up as they are, so they point at the
+                                // required gatekeeper's source rather than at this require
+                                if let Some(diagnostic) = e.downcast_ref::<Diagnostic>() {
+                                    return Err(LuaError::external(diagnostic.clone()));
+                                }
+                                Err(LuaError::RuntimeError(format!(
+                                    "Failed to load gatekeeper '{}': {}\nHint: Check that the gatekeeper exists and has valid syntax",
+                                    path_clone, e
+                                )))
+                            }
+                        }
+                    })?;
+
+                    // Return the loader function
+                    return Ok(loader);
+                }
+            }
+
+            // Fall back to shared modules in lib/, which may return any value
+            if let Some(lib_path) = crate::gatekeeper::get_lib_path(&gk_name).map_err(runtime_error)? {
+                let lib_name = format!("lib/{}", gk_name);
+                context
+                    .visit(&lib_name)
+                    .map_err(|e| LuaError::RuntimeError(e.to_string()))?;
+
+                let context_clone = context.clone();
+                let loader = lua_ctx.create_function(move |lua, args: LuaMultiValue| {
+                    let result = std::fs::read_to_string(&lib_path)
+                        .map_err(|e| {
+                            LuaError::RuntimeError(format!(
+                                "Failed to read module '{}' at {}: {}",
+                                lib_name,
+                                lib_path.display(),
+                                e
+                            ))
+                        })
+                        .and_then(|code| {
+                            lua.load(code)
+                                .set_name(format!("@{}", lib_path.display()))
+                                .call::<_, LuaMultiValue>(args)
+                        });
+                    context_clone.leave(&lib_name);
+                    Self::record_dependencies(lua, [lib_path.clone()]);
+                    result
+                })?;
+                return Ok(loader);
+            }
+
+            // Not found - return error message with paths tried
+            Err(LuaError::RuntimeError(format!(
+                "Gatekeeper '{}' not found (tried: {}, lib/{}.lua)",
+                module_name,
+                paths_to_try.join(".lua, ") + ".lua",
+                gk_name
+            )))
+        })
+        .map_err(|e| anyhow::anyhow!("Failed to create custom searcher: {}", e))?;
+
+        // Insert at the beginning of searchers table (index 1)
+        searchers.raw_insert(1, custom_searcher)
+            .map_err(|e| anyhow::anyhow!("Failed to insert custom searcher: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Timeout for active probes: 500ms unless given, and never more than 10s
+    fn probe_timeout(timeout_ms: Option<u64>) -> Duration {
+        Duration::from_millis(timeout_ms.unwrap_or(500).min(10_000))
+    }
+
+    /// Names that hostname checks match against: the system hostname and its FQDN
+    fn hostname_candidates() -> LuaResult<Vec<String>> {
+        let hostname = facts::host::hostname().map_err(runtime_error)?;
+        let fqdn = facts::host::fqdn(&facts::root(), &hostname);
+        if fqdn == hostname {
+            Ok(vec![hostname])
+        } else {
+            Ok(vec![hostname, fqdn])
+        }
+    }
+
+    /// Accept either a single string or a table of strings as a function argument
+    fn string_list(value: LuaValue, function: &str) -> LuaResult<Vec<String>> {
+        match value {
+            LuaValue::String(s) => Ok(vec![s.to_str()?.to_string()]),
+            LuaValue::Table(table) => table.sequence_values::<String>().collect(),
+            other => Err(LuaError::RuntimeError(format!(
+                "{}() expects a string or a table of strings, got {}",
+                function,
+                other.type_name()
+            ))),
+        }
+    }
+
+    /// Execute a Lua script and return the result
+    pub fn execute(&self, script: &str) -> Result<LuaGatekeeperResult> {
+        // Parse TTL from comment if present (-- ttl: 3600)
+        let ttl = Self::parse_ttl_comment(script);
+
+        // Time-based checks record when their answer could next change
+        self.lua.set_app_data(NextBoundary::default());
+        self.lua.set_app_data(Dependencies::default());
+        self.lua.set_app_data(Reason::default());
+        self.lua.set_app_data(CacheReads::default());
+
+        // Name the chunk after the file, so Lua reports locations as path:line
+        let (name, path) = match self.lua.app_data_ref::<Source>() {
+            Some(source) => (Some(source.name.clone()), source.path.display().to_string()),
+            None => (None, "script".to_string()),
+        };
+        let chunk_name = match name {
+            Some(_) => format!("@{}", path),
+            None => format!("={}", path),
+        };
+
+        // Execute the Lua script
+        let result: LuaValue = self
+            .lua
+            .load(script)
+            .set_name(chunk_name)
+            .eval()
+            .map_err(|e| Diagnostic::from_lua_error(&e, name.as_deref(), &path, script))?;
+
+        let boundary_ttl = self.boundary_ttl();
+        let dependencies = self
+            .lua
+            .app_data_ref::<Dependencies>()
+            .map(|dependencies| dependencies.0.clone())
+            .unwrap_or_default();
+        let reason = self
+            .lua
+            .app_data_ref::<Reason>()
+            .and_then(|reason| reason.0.clone());
+        let cache_dependencies = self
+            .lua
+            .app_data_ref::<CacheReads>()
+            .map(|reads| reads.0.clone())
+            .unwrap_or_default();
+
+        // Extract result
+        let mut result = match result {
+            // Table with value and optional ttl
+            LuaValue::Table(table) => {
+                let value = table.get::<_, LuaValue>("value").unwrap_or(LuaValue::Nil);
+                let value = Self::gatekeeper_value(&value).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Table must contain a 'value' field of type boolean, number or string"
+                    )
+                })??;
+                let table_ttl = table.get::<_, Option<u64>>("ttl").ok().flatten();
+                let table_reason = table
+                    .get::<_, Option<String>>("reason")
+                    .map_err(|_| anyhow::anyhow!("Table 'reason' field must be a string"))?;
+                LuaGatekeeperResult {
+                    value,
+                    ttl: Self::earliest_ttl(table_ttl.or(ttl), boundary_ttl),
+                    dependencies,
+                    reason: table_reason.or(reason),
+                    cache_dependencies,
+                    pending: None,
+                    changed_at: None,
+                }
+            }
+
+            // Plain boolean, number or string return
+            _ => match Self::gatekeeper_value(&result) {
+                Some(value) => LuaGatekeeperResult {
+                    value: value?,
+                    ttl: Self::earliest_ttl(ttl, boundary_ttl),
+                    dependencies,
+                    reason,
+                    cache_dependencies,
+                    pending: None,
+                    changed_at: None,
+                },
+                None => anyhow::bail!(
+                    "Lua script must return a boolean, number, string or table with 'value' field.\nGot: {:?}",
+                    result
+                ),
+            },
+        };
+
+        // Debounce and hold headers smooth the value against what was cached before
+        let hysteresis = Hysteresis::parse(script)?;
+        if !hysteresis.is_empty() {
+            let previous = self.lua.app_data_ref::<PreviousEntry>().map(|entry| entry.0.clone());
+            let now = Self::clock(&self.lua).timestamp().max(0) as u64;
+            let stabilized = hysteresis.apply(result.value, previous.as_ref(), now);
+            result.value = stabilized.value;
+            result.pending = stabilized.pending;
+            result.changed_at = stabilized.changed_at;
+            result.ttl = Self::earliest_ttl(result.ttl, stabilized.hold_remaining);
+        }
+
+        Ok(result)
+    }
+
+    /// Convert a returned Lua value, or `None` if it isn't a boolean, number or string
+    fn gatekeeper_value(value: &LuaValue) -> Option<Result<GatekeeperValue>> {
+        Some(match value {
+            LuaValue::Boolean(value) => Ok(GatekeeperValue::Bool(*value)),
+            LuaValue::Integer(value) => Ok(GatekeeperValue::Integer(*value)),
+            LuaValue::Number(value) if value.is_finite() => Ok(GatekeeperValue::Number(*value)),
+            LuaValue::Number(value) => Err(anyhow::anyhow!(
+                "Gatekeeper returned {}, numbers must be finite",
+                value
+            )),
+            LuaValue::String(value) => value
+                .to_str()
+                .map(|value| GatekeeperValue::String(value.to_string()))
+                .map_err(|_| anyhow::anyhow!("Gatekeeper returned a string that isn't valid UTF-8")),
+            _ => return None,
+        })
+    }
+
+    /// Convert a required gatekeeper's value back into Lua
+    fn value_to_lua<'lua>(lua: &'lua Lua, value: &GatekeeperValue) -> LuaResult<LuaValue<'lua>> {
+        Ok(match value {
+            GatekeeperValue::Bool(value) => LuaValue::Boolean(*value),
+            GatekeeperValue::Integer(value) => LuaValue::Integer(*value),
+            GatekeeperValue::Number(value) => LuaValue::Number(*value),
+            GatekeeperValue::String(value) => LuaValue::String(lua.create_string(value)?),
+        })
+    }
+
+    /// Seconds until the earliest recorded boundary, if any check recorded one
+    fn boundary_ttl(&self) -> Option<u64> {
+        let boundary = self.lua.app_data_ref::<NextBoundary>()?.0?;
+        let now = Self::clock(&self.lua).timestamp();
+        Some((boundary - now).max(1) as u64)
+    }
+
+    fn earliest_ttl(a: Option<u64>, b: Option<u64>) -> Option<u64> {
+        match (a, b) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// Parse TTL from comment like: -- ttl: 3600
+    fn parse_ttl_comment(script: &str) -> Option<u64> {
+        let re = Regex::new(r"^--\s*ttl:\s*(\d+)").ok()?;
+        for line in script.lines() {
+            if let Some(captures) = re.captures(line.trim())
+                && let Some(ttl_str) = captures.get(1)
+            {
+                return ttl_str.as_str().parse::<u64>().ok();
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_exists() {
+        let executor = LuaExecutor::new().unwrap();
+        let result = executor
+            .execute(r#"return file_exists("/etc/passwd")"#)
+            .unwrap();
+        // This file should exist on Linux systems
+        if cfg!(target_os = "linux") {
+            assert_eq!(result.value, true);
+        }
+    }
+
+    #[test]
+    fn test_file_not_exists() {
+        let executor = LuaExecutor::new().unwrap();
+        let result = executor
+            .execute(r#"return file_exists("/nonexistent/path/12345")"#)
+            .unwrap();
+        assert_eq!(result.value, false);
+    }
+
+    #[test]
+    fn test_file_functions_resolve_relative_to_script_dir() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("conf.d")).unwrap();
+        std::fs::write(dir.path().join("conf.d/work.conf"), "").unwrap();
+        std::fs::write(dir.path().join("settings.json"), r#"{"work": true}"#).unwrap();
+
+        let executor = LuaExecutor::new().unwrap();
+        executor.set_script_dir(dir.path());
+        let result = executor
+            .execute(
+                r#"
+                return is_dir("conf.d")
+                    and is_file("conf.d/work.conf")
+                    and not is_file("conf.d")
+                    and not is_symlink("conf.d/work.conf")
+                    and not is_executable("conf.d/work.conf")
+                    and glob_exists("conf.d/*.conf")
+                    and not glob_exists("conf.d/*.ini")
+                    and file_newer_than("conf.d/work.conf", 3600)
+                    and not file_newer_than("missing.conf", 3600)
+                    and json_eq("settings.json", "work", true)
+            "#,
+            )
+            .unwrap();
+        assert_eq!(result.value, true);
+    }
+
+    #[test]
+    fn test_file_functions_expand_variables() {
+        let executor = LuaExecutor::new().unwrap();
+        let result = executor
+            .execute(
+                r#"
+                return file_exists("$PATH_THAT_IS_NOT_SET_12345/x") == false
+                    and is_dir("~")
+                    and glob_exists("~")
+            "#,
+            )
+            .unwrap();
+        assert_eq!(result.value, true);
+    }
+
+    #[test]
+    fn test_os_check() {
+        let executor = LuaExecutor::new().unwrap();
+        let result = executor.execute(r#"return os("linux")"#).unwrap();
+        assert_eq!(result.value, cfg!(target_os = "linux"));
+    }
+
+    #[test]
+    fn test_hostname_list_and_glob() {
+        let executor = LuaExecutor::new().unwrap();
+        let result = executor
+            .execute(r#"return hostname({"this-hostname-should-never-exist-12345", short_hostname()})"#)
+            .unwrap();
+        assert_eq!(result.value, true);
+
+        let result = executor
+            .execute(r#"return hostname(short_hostname():sub(1, 1) .. "*")"#)
+            .unwrap();
+        assert_eq!(result.value, true);
+
+        let result = executor
+            .execute(r#"return hostname({"nope-*", "also-nope"})"#)
+            .unwrap();
+        assert_eq!(result.value, false);
+    }
+
+    #[test]
+    fn test_hostname_match_and_fqdn() {
+        let executor = LuaExecutor::new().unwrap();
+        let result = executor
+            .execute(r#"return hostname_match("^" .. short_hostname())"#)
+            .unwrap();
+        assert_eq!(result.value, true);
+
+        let result = executor
+            .execute(r#"return fqdn():sub(1, #short_hostname()) == short_hostname()"#)
+            .unwrap();
+        assert_eq!(result.value, true);
+
+        assert!(executor.execute(r#"return hostname_match("(")"#).is_err());
+    }
+
+    #[test]
+    fn test_user_functions() {
+        let executor = LuaExecutor::new().unwrap();
+        let result = executor
+            .execute(r#"return user("this-user-should-never-exist-12345")"#)
+            .unwrap();
+        assert_eq!(result.value, false);
+
+        let result = executor
+            .execute(r#"return in_group("this-group-should-never-exist-12345")"#)
+            .unwrap();
+        assert_eq!(result.value, false);
+
+        if cfg!(unix) {
+            let result = executor
+                .execute(r#"return is_root() == (uid() == 0)"#)
+                .unwrap();
+            assert_eq!(result.value, true);
+        }
+    }
+
+    #[test]
+    fn test_distro_functions() {
+        let executor = LuaExecutor::new().unwrap();
+        let result = executor
+            .execute(r#"return distro("this-distro-should-never-exist")"#)
+            .unwrap();
+        assert_eq!(result.value, false);
+
+        let result = executor
+            .execute(
+                r#"return type(distro_like("debian")) == "boolean"
+                    and type(distro_version_at_least("22.04")) == "boolean""#,
+            )
+            .unwrap();
+        assert_eq!(result.value, true);
+        assert!(executor.execute(r#"return distro_version_at_least("latest")"#).is_err());
+
+        let result = executor
+            .execute(r#"local v = kernel_version() return v == nil or type(v) == "string""#)
+            .unwrap();
+        assert_eq!(result.value, true);
+    }
+
+    #[test]
+    fn test_hardware_functions() {
+        let executor = LuaExecutor::new().unwrap();
+        let result = executor
+            .execute(&format!(r#"return arch({{"nope", "{}"}})"#, std::env::consts::ARCH))
+            .unwrap();
+        assert_eq!(result.value, true);
+
+        let result = executor
+            .execute(
+                r#"local mem = memory_total_mb()
+                return cpu_count() >= 1
+                    and (mem == nil or mem > 0)
+                    and type(has_battery()) == "boolean""#,
+            )
+            .unwrap();
+        assert_eq!(result.value, true);
+    }
+
+    #[test]
+    fn test_environment_functions() {
+        let executor = LuaExecutor::new().unwrap();
+        let result = executor
+            .execute(
+                r#"for _, detect in ipairs({in_container, in_wsl, in_vm, in_ssh, in_tmux}) do
+                    if type(detect()) ~= "boolean" then return false end
+                end
+                return true"#,
+            )
+            .unwrap();
+        assert_eq!(result.value, true);
+    }
+
+    #[test]
+    fn test_network_functions() {
+        let executor = LuaExecutor::new().unwrap();
+        let result = executor
+            .execute(
+                r#"return not interface_up("this-interface-should-never-exist")
+                    and not default_route_via("this-interface-should-never-exist")
+                    and not has_ip_in("192.0.2.255/32")"#,
+            )
+            .unwrap();
+        assert_eq!(result.value, true);
+
+        if cfg!(target_os = "linux") {
+            let result = executor.execute(r#"return has_ip_in({"127.0.0.0/8", "::1/128"})"#).unwrap();
+            assert_eq!(result.value, true);
+        }
+
+        assert!(executor.execute(r#"return has_ip_in("not-a-cidr")"#).is_err());
+        // Checked up front, not only once an address of the same family turns up
+        assert!(executor.execute(r#"return has_ip_in({"0.0.0.0/0", "::/0", "10.0.0.0/33"})"#).is_err());
+    }
+
+    #[test]
+    fn test_system_functions() {
+        let executor = LuaExecutor::new().unwrap();
+        let result = executor
+            .execute(
+                r#"return not process_running("this-process-should-never-exist")
+                    and not is_mounted("/this/path/should/never/exist")
+                    and not mount_type("/", "this-fs-should-never-exist")"#,
+            )
+            .unwrap();
+        assert_eq!(result.value, true);
+
+        if cfg!(target_os = "linux") {
+            let exe = std::env::current_exe().unwrap();
+            let name = exe.file_name().unwrap().to_string_lossy();
+            let script = format!(r#"return process_running("{}") and is_mounted("/")"#, name);
+            assert_eq!(executor.execute(&script).unwrap().value, true);
+        }
+    }
+
+    #[test]
+    fn test_git_functions() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(dir.path().join("work/.git")).unwrap();
+        std::fs::write(dir.path().join("work/.git/HEAD"), "ref: refs/heads/main\n").unwrap();
+        std::fs::write(
+            dir.path().join("work/.git/config"),
+            "[remote \"origin\"]\n\turl = git@github.com:corp/tools.git\n",
+        )
+        .unwrap();
+
+        let executor = LuaExecutor::new().unwrap();
+        executor.set_script_dir(dir.path());
+        let result = executor
+            .execute(
+                r#"
+                return in_git_repo("work")
+                    and not in_git_repo(".")
+                    and git_branch("work") == "main"
+                    and git_branch(".") == nil
+                    and git_remote_matches("github%.com[:/]corp/", "work") == false
+                    and git_remote_matches("github\\.com[:/]corp/", "work")
+            "#,
+            )
+            .unwrap();
+        assert_eq!(result.value, true);
+    }
+
+    #[test]
+    fn test_tcp_connect() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let executor = LuaExecutor::new().unwrap();
+
+        let script = format!(r#"return tcp_connect("127.0.0.1", {}, 200)"#, port);
+        assert_eq!(executor.execute(&script).unwrap().value, true);
+
+        // Memoized: the answer doesn't change within one run
+        drop(listener);
+        assert_eq!(executor.execute(&script).unwrap().value, true);
+    }
+
+    #[test]
+    fn test_tcp_connect_timeout() {
+        // TEST-NET-1 is never routed, so this gives up after timeout_ms rather than
+        // waiting out the OS connect timeout
+        let executor = LuaExecutor::new().unwrap();
+        let started = std::time::Instant::now();
+        let result = executor.execute(r#"return tcp_connect("192.0.2.1", 9, 200)"#).unwrap();
+        assert_eq!(result.value, false);
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        // Oversized timeouts are clamped to 10s
+        assert_eq!(LuaExecutor::probe_timeout(Some(600_000)), Duration::from_secs(10));
+        assert_eq!(LuaExecutor::probe_timeout(Some(200)), Duration::from_millis(200));
+        assert_eq!(LuaExecutor::probe_timeout(None), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_version_comparisons() {
+        let executor = LuaExecutor::new().unwrap();
+        let result = executor
+            .execute(
+                r#"
+                local nvim = version("v0.10.2")
+                return nvim >= "0.10"
+                    and nvim > version("0.9.5")
+                    and "0.11" > nvim
+                    and version("1.2") == version("1.2.0")
+                    and version("2.0.0-rc.1") < "2.0.0"
+                    and version(3) < "3.1"
+                    and nvim.major == 0 and nvim.minor == 10 and nvim.patch == 2
+                    and tostring(nvim) == "v0.10.2"
+            "#,
+            )
+            .unwrap();
+        assert_eq!(result.value, true);
+
+        let err = executor.execute(r#"return version("nightly") > "1.0""#).unwrap_err();
+        assert!(format!("{:#}", err).contains("Invalid version 'nightly'"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_version() {
+        let executor = LuaExecutor::new().unwrap();
+        let result = executor
+            .execute(
+                r#"
+                local v = command_version("sh", {"-c", "echo 'tool version 2.43.0 (build 7)'"})
+                return v >= "2.40"
+                    and command_version("sh", {"-c", "echo v1.2.3 >&2"}) == version("1.2.3")
+                    and command_version("sh", {"-c", "echo no version"}) == nil
+                    and command_version("dotgk-no-such-command", "--version") == nil
+            "#,
+            )
+            .unwrap();
+        assert_eq!(result.value, true);
+    }
+
+    fn executor_at(now: &str) -> LuaExecutor {
+        let now = chrono::DateTime::parse_from_rfc3339(now).unwrap();
+        LuaExecutor::with_clock(Clock::fixed(now, Some("America/New_York"))).unwrap()
+    }
+
+    #[test]
+    fn test_time_functions() {
+        // Wednesday
+        let executor = executor_at("2026-10-14T10:30:00-04:00");
+        let result = executor
+            .execute(r#"return weekday("mon", "fri") and time_between("09:00", "18:00")"#)
+            .unwrap();
+        assert_eq!(result.value, true);
+        // Expires at 18:00, the next point where the answer could change
+        assert_eq!(result.ttl, Some(7 * 3600 + 30 * 60));
+
+        let result = executor
+            .execute(r#"return weekday({"sat", "sun"})"#)
+            .unwrap();
+        assert_eq!(result.value, false);
+        // Expires at midnight
+        assert_eq!(result.ttl, Some(13 * 3600 + 30 * 60));
+
+        let result = executor
+            .execute(r#"return timezone("America/New_York") and date_before("2026-12-31")"#)
+            .unwrap();
+        assert_eq!(result.value, true);
+    }
+
+    #[test]
+    fn test_time_functions_respect_explicit_ttl() {
+        let executor = executor_at("2026-10-14T10:30:00-04:00");
+        let result = executor
+            .execute(
+                r#"
+            -- ttl: 60
+            return time_between("09:00", "18:00")
+        "#,
+            )
+            .unwrap();
+        assert_eq!(result.ttl, Some(60));
+
+        let result = executor.execute(r#"return date_before("2020-01-01")"#).unwrap();
+        assert_eq!(result.value, false);
+        assert_eq!(result.ttl, None);
+
+        assert!(executor.execute(r#"return weekday("someday")"#).is_err());
+    }
+
+    #[test]
+    fn test_rollout() {
+        let executor = LuaExecutor::new().unwrap();
+        let result = executor
+            .execute(
+                r#"
+            local first = rollout("new_prompt", 25)
+            for _ = 1, 10 do
+                if rollout("new_prompt", 25) ~= first then return false end
+            end
+            return rollout("new_prompt", 100) and not rollout("new_prompt", 0)
+        "#,
+            )
+            .unwrap();
+        assert_eq!(result.value, true);
+
+        let result = executor
+            .execute(
+                r#"
+            local enabled = 0
+            for i = 1, 1000 do
+                if rollout("new_prompt", 50, { key = "machine-" .. i }) then enabled = enabled + 1 end
+            end
+            return enabled > 400 and enabled < 600
+        "#,
+            )
+            .unwrap();
+        assert_eq!(result.value, true);
+
+        assert!(executor.execute(r#"return rollout("new_prompt", 150)"#).is_err());
+    }
+
+    #[test]
+    fn test_data_functions() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let json = dir.path().join("cpe_info.json");
+        std::fs::write(&json, r#"{"cpe": {"tags": ["corp", "laptop"], "managed": true, "tier": 2}}"#).unwrap();
+        let ini = dir.path().join("settings.ini");
+        std::fs::write(&ini, "[profile]\nmanaged = true\ntier = 2\n").unwrap();
+
+        let executor = LuaExecutor::new().unwrap();
+        let script = format!(
+            r#"
+            local tags = json_get("{json}", "cpe.tags")
+            return tags[2] == "laptop"
+                and json_get("{json}", "cpe.tags[0]") == "corp"
+                and json_get("{json}", "cpe.missing") == nil
+                and json_get("{dir}/missing.json", "cpe") == nil
+                and json_eq("{json}", "cpe.managed", true)
+                and json_eq("{json}", "cpe.tier", 2)
+                and ini_eq("{ini}", "profile.managed", true)
+                and ini_eq("{ini}", "profile.tier", 2)
+                and not json_eq("{json}", "cpe.tier", "3")
+        "#,
+            json = json.display(),
+            ini = ini.display(),
+            dir = dir.path().display(),
+        );
+        assert_eq!(executor.execute(&script).unwrap().value, true);
+    }
+
+    #[test]
+    fn test_data_parse_error_names_file_and_key() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let broken = dir.path().join("broken.toml");
+        std::fs::write(&broken, "key = [unterminated").unwrap();
+
+        let executor = LuaExecutor::new().unwrap();
+        let err = executor
+            .execute(&format!(r#"return toml_get("{}", "key")"#, broken.display()))
+            .unwrap_err();
+        let message = format!("{:#}", err);
+        assert!(message.contains("broken.toml"));
+        assert!(message.contains("toml_get"));
+        assert!(message.contains("'key'"));
+    }
+
+    #[test]
+    fn test_any_combinator() {
+        let executor = LuaExecutor::new().unwrap();
+        let result = executor
+            .execute(
+                r#"
+            return any({
+                file_exists("/nonexistent1"),
+                file_exists("/etc/passwd"),
+            })
+        "#,
+            )
+            .unwrap();
+        if cfg!(target_os = "linux") {
+            assert_eq!(result.value, true);
+        }
+    }
+
+    #[test]
+    fn test_all_combinator() {
+        let executor = LuaExecutor::new().unwrap();
+        let result = executor
             .execute(
                 r#"
             return all({
@@ -405,6 +2166,280 @@ mod tests {
         assert_eq!(result.value, true);
     }
 
+    #[test]
+    fn test_combinators_short_circuit() {
+        let executor = LuaExecutor::new().unwrap();
+        let result = executor
+            .execute(
+                r#"
+                local calls = 0
+                local function check(value)
+                    return function()
+                        calls = calls + 1
+                        return value
+                    end
+                end
+                local function never()
+                    error("should not run")
+                end
+
+                local results = {
+                    any({ false, check(true), never }),
+                    not all({ check(true), check(false), never }),
+                    not none({ check(false), true, never }),
+                    all({ true, check(true) }),
+                }
+                return all(results) and calls == 5
+            "#,
+            )
+            .unwrap();
+        assert_eq!(result.value, true);
+    }
+
+    #[test]
+    fn test_counting_combinators() {
+        let executor = LuaExecutor::new().unwrap();
+        let result = executor
+            .execute(
+                r#"
+                local function never()
+                    error("should not run")
+                end
+                return all({
+                    at_least(2, { true, false, true, never }),
+                    not at_least(3, { false, false, true, never }),
+                    at_most(1, { false, true, false }),
+                    not at_most(1, { true, true, never }),
+                    exactly(2, { true, false, true }),
+                    not exactly(2, { true, true, true, never }),
+                    exactly(0, {}),
+                    xor({ false, true, false }),
+                    not xor({ true, false, true, never }),
+                    not xor({ false, false }),
+                    majority({ true, false, true, true, never }),
+                    not majority({ true, false }),
+                    not majority({ false, false, never, never }),
+                    score({ {true, 2}, {false, 5}, {function() return true end, 0.5} }) == 2.5,
+                })
+            "#,
+            )
+            .unwrap();
+        assert_eq!(result.value, true);
+    }
+
+    #[test]
+    fn test_combinators_reject_non_booleans() {
+        let executor = LuaExecutor::new().unwrap();
+        let err = executor
+            .execute(r#"return any({ false, "yes" })"#)
+            .unwrap_err();
+        assert!(format!("{:#}", err).contains("any(): check #2 is string"));
+
+        let err = executor
+            .execute(r#"return all({ true, function() return nil end })"#)
+            .unwrap_err();
+        assert!(format!("{:#}", err).contains("all(): check #2 returned nil"));
+
+        let err = executor
+            .execute(r#"return at_least(1, { false, false, 1 })"#)
+            .unwrap_err();
+        assert!(format!("{:#}", err).contains("at_least(): check #3 is integer"));
+
+        let err = executor
+            .execute(r#"return score({ {true, 1}, {true, "high"} }) > 1"#)
+            .unwrap_err();
+        assert!(format!("{:#}", err).contains("score(): weight of check #2 is string"));
+    }
+
+    #[test]
+    fn test_cached_values() {
+        use crate::cache::UpdateType;
+        use crate::cache::cache_result_with_ttl;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let cache_path = dir.path().join("dotgk.json");
+        cache_result_with_ttl("manual/work_mode", true, Some(cache_path.clone()), UpdateType::Set, None)
+            .unwrap();
+        // A TTL of zero expires immediately, so the entry reads as unset
+        cache_result_with_ttl("expired", true, Some(cache_path.clone()), UpdateType::Set, Some(0))
+            .unwrap();
+
+        let executor = LuaExecutor::new().unwrap();
+        executor.set_cache_path(&cache_path);
+
+        let result = executor
+            .execute(r#"return cached("manual/work_mode", false)"#)
+            .unwrap();
+        assert_eq!(result.value, true);
+        assert_eq!(
+            result.cache_dependencies,
+            BTreeMap::from([("manual/work_mode".to_string(), Some(true.into()))])
+        );
+
+        let result = executor
+            .execute(r#"return cached("missing", "fallback")"#)
+            .unwrap();
+        assert_eq!(result.value, GatekeeperValue::String("fallback".to_string()));
+        assert_eq!(
+            result.cache_dependencies,
+            BTreeMap::from([("missing".to_string(), None)])
+        );
+
+        let result = executor
+            .execute(r#"return is_set("manual/work_mode") and not is_set("expired")"#)
+            .unwrap();
+        assert_eq!(result.value, true);
+        assert_eq!(result.cache_dependencies.len(), 2);
+
+        // Reads don't leak between evaluations
+        let result = executor.execute("return true").unwrap();
+        assert!(result.cache_dependencies.is_empty());
+    }
+
+    #[test]
+    fn test_previous() {
+        let executor = LuaExecutor::new().unwrap();
+        let result = executor.execute("return previous() == nil").unwrap();
+        assert_eq!(result.value, true);
+
+        executor.set_previous(Previous {
+            value: GatekeeperValue::String("dark".to_string()),
+            ts: 1000,
+            changed_at: 1000,
+            pending: None,
+        });
+        let result = executor
+            .execute(r#"local value, ts = previous() return value == "dark" and ts == 1000"#)
+            .unwrap();
+        assert_eq!(result.value, true);
+    }
+
+    #[test]
+    fn test_hysteresis_headers() {
+        let executor = executor_at("2026-10-14T10:30:00-04:00");
+        let now = LuaExecutor::clock(&executor.lua).timestamp() as u64;
+
+        // Turned true 5 minutes ago, so a false is held for another 5
+        executor.set_previous(Previous {
+            value: true.into(),
+            ts: now - 60,
+            changed_at: now - 300,
+            pending: None,
+        });
+        let result = executor
+            .execute("-- hold_true: 10m\n-- ttl: 3600\nreturn false")
+            .unwrap();
+        assert_eq!(result.value, true);
+        assert_eq!(result.changed_at, Some(now - 300));
+        assert_eq!(result.ttl, Some(300));
+
+        // The first disagreeing evaluation is held back by debounce
+        let result = executor.execute("-- debounce: 2\nreturn false").unwrap();
+        assert_eq!(result.value, true);
+        assert_eq!(
+            result.pending,
+            Some(Pending {
+                value: false.into(),
+                count: 1
+            })
+        );
+
+        // Without headers the previous value doesn't matter
+        let result = executor.execute("return false").unwrap();
+        assert_eq!(result.value, false);
+        assert_eq!(result.pending, None);
+    }
+
+    #[test]
+    fn test_log_functions() {
+        use std::sync::Arc;
+        use std::sync::Mutex;
+
+        #[derive(Clone, Default)]
+        struct Capture(Arc<Mutex<Vec<u8>>>);
+
+        impl std::io::Write for Capture {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let capture = Capture::default();
+        let writer = capture.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_env_filter(format!("{}=debug", SCRIPT_LOG_TARGET))
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+
+        let result = tracing::subscriber::with_default(subscriber, || {
+            let _span =
+                tracing::info_span!(target: SCRIPT_LOG_TARGET, "gatekeeper", name = "work/vpn")
+                    .entered();
+            LuaExecutor::new()
+                .unwrap()
+                .execute(
+                    r#"
+                    log.debug("checking", 42, nil)
+                    log.info("ok")
+                    log.warn("careful")
+                    print("stray")
+                    return true
+                "#,
+                )
+                .unwrap()
+        });
+        assert_eq!(result.value, true);
+
+        let output = String::from_utf8(capture.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("DEBUG gatekeeper{name=\"work/vpn\"}: dotgk::script: checking\t42\tnil"));
+        assert!(output.contains("INFO gatekeeper{name=\"work/vpn\"}: dotgk::script: ok"));
+        assert!(output.contains("WARN gatekeeper{name=\"work/vpn\"}: dotgk::script: careful"));
+        // print goes straight to stderr, not through tracing
+        assert!(!output.contains("stray"));
+    }
+
+    #[test]
+    fn test_reasons() {
+        let executor = LuaExecutor::new().unwrap();
+
+        let result = executor
+            .execute(r#"return { value = false, reason = "not on corp VPN" }"#)
+            .unwrap();
+        assert_eq!(result.reason.as_deref(), Some("not on corp VPN"));
+
+        let result = executor
+            .execute(r#"return false or because("first") or because("not on corp VPN")"#)
+            .unwrap();
+        assert_eq!(result.value, false);
+        assert_eq!(result.reason.as_deref(), Some("not on corp VPN"));
+
+        // An explicit reason field wins over because()
+        let result = executor
+            .execute(r#"because("ignored") return { value = true, reason = "explicit" }"#)
+            .unwrap();
+        assert_eq!(result.reason.as_deref(), Some("explicit"));
+
+        // Reasons don't leak between evaluations
+        let result = executor.execute("return true").unwrap();
+        assert_eq!(result.reason, None);
+    }
+
+    #[test]
+    fn test_non_finite_numbers_are_rejected() {
+        let executor = LuaExecutor::new().unwrap();
+        let err = executor.execute("return 0/0").unwrap_err();
+        assert!(err.to_string().contains("numbers must be finite"));
+
+        let err = executor.execute("return { value = {} }").unwrap_err();
+        assert!(err.to_string().contains("'value' field of type boolean, number or string"));
+    }
+
     #[test]
     fn test_ttl_parsing() {
         let executor = LuaExecutor::new().unwrap();
@@ -1,8 +1,12 @@
 mod cache;
 mod cli;
+mod diagnostic;
+mod facts;
 mod gatekeeper;
+mod hysteresis;
 mod lua_executor;
 mod settings;
+mod value;
 
 use anyhow::Result;
 use clap::Parser;
@@ -14,27 +18,37 @@ use tracing::info;
 use tracing::instrument;
 use tracing_subscriber::EnvFilter;
 
-use crate::gatekeeper::load_and_evaluate_gatekeeper;
+use crate::gatekeeper::Variables;
+use crate::gatekeeper::cache_key;
+use crate::gatekeeper::load_and_evaluate_gatekeeper_with_previous;
+use crate::lua_executor::SCRIPT_LOG_TARGET;
 
 #[instrument]
-fn evaluate_command(name: String, no_cache: bool) -> Result<()> {
+fn evaluate_command(name: String, no_cache: bool, vars: Variables) -> Result<()> {
     info!("Evaluating gatekeeper: {}", name);
 
-    let gatekeeper_result = load_and_evaluate_gatekeeper(&name)?;
-    let result = gatekeeper_result.value;
+    // Each set of variables gets its own cache entry
+    let key = cache_key(&name, &vars);
+
+    // The last cached entry feeds previous() and debounce/hold headers
+    let previous = cache::load_entry(&key, None);
+    let gatekeeper_result =
+        load_and_evaluate_gatekeeper_with_previous(&name, previous.as_ref(), &vars)?;
+    let result = &gatekeeper_result.value;
     info!("Evaluation result: {}", result);
     println!("{}", result);
 
     // Cache the result unless --no-cache is specified
-    if !no_cache {
-        let ttl = gatekeeper_result.ttl;
-
-        if let Err(e) =
-            cache::cache_result_with_ttl(&name, result, None, cache::UpdateType::Evaluate, ttl)
-        {
-            // Don't fail the command if caching fails, just log the error
-            tracing::warn!("Failed to cache evaluation result: {}", e);
-        }
+    if !no_cache
+        && let Err(e) = cache::cache_gatekeeper_result(
+            &key,
+            &gatekeeper_result,
+            None,
+            cache::UpdateType::Evaluate,
+        )
+    {
+        // Don't fail the command if caching fails, just log the error
+        tracing::warn!("Failed to cache evaluation result: {}", e);
     }
 
     Ok(())
@@ -122,17 +136,31 @@ fn main() -> Result<()> {
 
     // Initialize tracing with the appropriate default level
     // RUST_LOG environment variable can still override this default
-    let env_filter =
+    let mut env_filter =
         EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_level));
 
-    tracing_subscriber::fmt().with_env_filter(env_filter).init();
-
+    // `evaluate --verbose` shows everything gatekeeper scripts log
     let args = Args::parse();
+    if let Command::Evaluate { verbose: true, .. } = args.command {
+        env_filter = env_filter.add_directive(format!("{}=debug", SCRIPT_LOG_TARGET).parse()?);
+    }
+
+    // Log to stderr, since stdout carries gatekeeper values
+    tracing_subscriber::fmt()
+        .with_env_filter(env_filter)
+        .with_writer(std::io::stderr)
+        .init();
+
     debug!("Parsed args: {:?}", args);
 
     match args.command {
-        Command::Evaluate { name, no_cache } => evaluate_command(name, no_cache),
-        Command::Get { name } => cache::get_command(name, None),
+        Command::Evaluate {
+            name,
+            no_cache,
+            vars,
+            verbose: _,
+        } => evaluate_command(name, no_cache, vars.into_iter().collect()),
+        Command::Get { name, verbose } => cache::get_command(name, None, verbose),
         Command::Set { name, value, ttl } => {
             let parsed_value = match value.to_lowercase().as_str() {
                 "true" | "1" | "yes" | "on" => true,
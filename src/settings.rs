@@ -9,21 +9,13 @@ use tracing::debug;
 
 use crate::gatekeeper::get_config_dir;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct Settings {
     /// List of enabled cache formats to generate
     #[serde(default)]
     pub enabled_cache_formats: Vec<String>,
 }
 
-impl Default for Settings {
-    fn default() -> Self {
-        Self {
-            enabled_cache_formats: Vec::new(),
-        }
-    }
-}
-
 pub fn get_settings_path() -> Result<PathBuf> {
     let mut config_dir = get_config_dir()?;
     config_dir.push("settings.json");
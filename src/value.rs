@@ -0,0 +1,75 @@
+use std::fmt;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Value produced by a gatekeeper: a boolean for on/off checks, or a string
+/// or number for multivariate gatekeepers ("dark", 4).
+///
+/// Serialized untagged, so boolean entries in existing caches read back unchanged.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum GatekeeperValue {
+    Bool(bool),
+    Integer(i64),
+    Number(f64),
+    String(String),
+}
+
+impl From<bool> for GatekeeperValue {
+    fn from(value: bool) -> Self {
+        GatekeeperValue::Bool(value)
+    }
+}
+
+impl PartialEq<bool> for GatekeeperValue {
+    fn eq(&self, other: &bool) -> bool {
+        *self == GatekeeperValue::Bool(*other)
+    }
+}
+
+impl fmt::Display for GatekeeperValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GatekeeperValue::Bool(value) => write!(f, "{}", value),
+            GatekeeperValue::Integer(value) => write!(f, "{}", value),
+            GatekeeperValue::Number(value) => write!(f, "{}", value),
+            GatekeeperValue::String(value) => f.write_str(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(GatekeeperValue::Bool(true).to_string(), "true");
+        assert_eq!(GatekeeperValue::Integer(4).to_string(), "4");
+        assert_eq!(GatekeeperValue::Number(0.5).to_string(), "0.5");
+        assert_eq!(GatekeeperValue::String("dark".into()).to_string(), "dark");
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let values = vec![
+            GatekeeperValue::Bool(false),
+            GatekeeperValue::Integer(4),
+            GatekeeperValue::Number(1.5),
+            GatekeeperValue::String("dark".into()),
+        ];
+        let json = serde_json::to_string(&values).unwrap();
+        assert_eq!(json, r#"[false,4,1.5,"dark"]"#);
+        let parsed: Vec<GatekeeperValue> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, values);
+    }
+
+    #[test]
+    fn test_bool_comparisons() {
+        assert_eq!(GatekeeperValue::Bool(true), true);
+        assert_ne!(GatekeeperValue::String("true".into()), true);
+        assert_eq!(GatekeeperValue::Bool(false), false);
+        assert_ne!(GatekeeperValue::Integer(0), false);
+    }
+}
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use dotgk::GatekeeperValue;
 use dotgk::lua_executor::LuaExecutor;
 
 #[test]
@@ -185,9 +186,34 @@ fn test_lua_syntax_error() {
 fn test_lua_wrong_return_type() {
     let executor = LuaExecutor::new().unwrap();
 
-    let result = executor.execute(r#"return "string value""#);
+    let result = executor.execute(r#"return function() end"#);
     assert!(result.is_err());
 
     let err_msg = result.unwrap_err().to_string();
-    assert!(err_msg.contains("must return a boolean"));
+    assert!(err_msg.starts_with(
+        "Lua script must return a boolean, number, string or table with 'value' field.\nGot: "
+    ));
+
+    let result = executor.execute(r#"return { ttl = 60 }"#);
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "Table must contain a 'value' field of type boolean, number or string"
+    );
+}
+
+#[test]
+fn test_lua_string_and_number_values() -> Result<()> {
+    let executor = LuaExecutor::new()?;
+
+    let result = executor.execute(r#"return "dark""#)?;
+    assert_eq!(result.value, GatekeeperValue::String("dark".to_string()));
+
+    let result = executor.execute(r#"return 4"#)?;
+    assert_eq!(result.value, GatekeeperValue::Integer(4));
+
+    let result = executor.execute(r#"return { value = 0.5, ttl = 60 }"#)?;
+    assert_eq!(result.value, GatekeeperValue::Number(0.5));
+    assert_eq!(result.ttl, Some(60));
+
+    Ok(())
 }